@@ -0,0 +1,123 @@
+use binary_data::{BigEndian, BinFile, BinReader, BinSeek, ReadBytes, WriteBytes};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use std::io::Write;
+
+const FILE_LEN: usize = 256 * 1024;
+const BUFFER_SIZE: usize = 8 * 1024;
+
+fn test_file() -> tempfile::NamedTempFile {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    let data: Vec<u8> = (0..FILE_LEN as u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+        .collect();
+    file.write_all(&data).unwrap();
+    file
+}
+
+/// Parses the file as records of a u8 tag, a u16 length and a u32 value.
+fn parse_fields(file: &mut BinFile) -> u64 {
+    let mut sum = 0u64;
+    while file.pos().unwrap() + 7 <= FILE_LEN {
+        sum += file.read_u8().unwrap() as u64;
+        sum += file.read_u16::<BigEndian>().unwrap() as u64;
+        sum += file.read_u32::<BigEndian>().unwrap() as u64;
+    }
+    sum
+}
+
+fn bench_read_fields(c: &mut Criterion) {
+    let temp = test_file();
+    let mut group = c.benchmark_group("bin_file_read_fields");
+    group.throughput(Throughput::Bytes(FILE_LEN as u64));
+
+    group.bench_function("unbuffered", |b| {
+        b.iter(|| {
+            let mut file = BinReader::open(temp.path()).unwrap();
+            black_box(parse_fields(&mut file))
+        })
+    });
+    group.bench_function("buffered", |b| {
+        b.iter(|| {
+            let mut file = BinReader::open(temp.path())
+                .unwrap()
+                .buffered(BUFFER_SIZE)
+                .unwrap();
+            black_box(parse_fields(&mut file))
+        })
+    });
+    group.finish();
+}
+
+/// Reads a header, peeks a field ahead of it and goes back, as record parsers do.
+fn peek_fields(file: &mut BinFile) -> u64 {
+    let mut sum = 0u64;
+    let mut pos = 0;
+    while pos + 16 <= FILE_LEN {
+        file.seek(pos + 12).unwrap();
+        sum += file.read_u32::<BigEndian>().unwrap() as u64;
+        file.seek(pos).unwrap();
+        sum += file.read_u16::<BigEndian>().unwrap() as u64;
+        pos += 16;
+    }
+    sum
+}
+
+fn bench_seek_fields(c: &mut Criterion) {
+    let temp = test_file();
+    let mut group = c.benchmark_group("bin_file_seek_fields");
+    group.throughput(Throughput::Bytes(FILE_LEN as u64));
+
+    group.bench_function("unbuffered", |b| {
+        b.iter(|| {
+            let mut file = BinReader::open(temp.path()).unwrap();
+            black_box(peek_fields(&mut file))
+        })
+    });
+    group.bench_function("buffered", |b| {
+        b.iter(|| {
+            let mut file = BinReader::open(temp.path())
+                .unwrap()
+                .buffered(BUFFER_SIZE)
+                .unwrap();
+            black_box(peek_fields(&mut file))
+        })
+    });
+    group.finish();
+}
+
+fn write_fields(file: &mut BinFile) {
+    for i in 0..(FILE_LEN / 7) as u32 {
+        file.write_u8(i as u8).unwrap();
+        file.write_u16::<BigEndian>(i as u16).unwrap();
+        file.write_u32::<BigEndian>(i).unwrap();
+    }
+    file.flush().unwrap();
+}
+
+fn bench_write_fields(c: &mut Criterion) {
+    let temp = test_file();
+    let mut group = c.benchmark_group("bin_file_write_fields");
+    group.throughput(Throughput::Bytes(FILE_LEN as u64));
+
+    for (name, capacity) in [("unbuffered", 0), ("buffered", BUFFER_SIZE)] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut file = BinFile::options()
+                    .write(true)
+                    .buffered(capacity)
+                    .open(temp.path())
+                    .unwrap();
+                write_fields(&mut file)
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_read_fields,
+    bench_seek_fields,
+    bench_write_fields
+);
+criterion_main!(benches);
@@ -0,0 +1,252 @@
+use binary_data::{BinMemoryBuffer, BitOrder, BitReader};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+const DATA_LEN: usize = 1024 * 1024;
+
+/// The byte-by-byte `BitReader` implementation that the cached reader replaced,
+/// kept here as a baseline for the comparison.
+mod legacy {
+    use binary_data::{BinSeek, Error, Result};
+    use std::io::Read;
+
+    pub struct BitReader<R: Read + BinSeek> {
+        reader: R,
+        buffer: Vec<u8>,
+        pos: usize,
+        cap: usize,
+        bit_pos: u8,
+        buffer_start: u64,
+    }
+
+    impl<R: Read + BinSeek> BitReader<R> {
+        pub fn new(mut reader: R) -> Self {
+            let buffer_start = reader.pos().unwrap_or(0) as u64;
+            Self {
+                reader,
+                buffer: vec![0; 8 * 1024],
+                pos: 0,
+                cap: 0,
+                bit_pos: 0,
+                buffer_start,
+            }
+        }
+
+        fn fill_buf(&mut self) -> Result<()> {
+            if self.pos < self.cap {
+                self.reader
+                    .seek((self.buffer_start + self.pos as u64) as usize)?;
+            }
+            self.buffer_start = self.reader.pos()? as u64;
+            self.cap = self.reader.read(&mut self.buffer)?;
+            self.pos = 0;
+            Ok(())
+        }
+
+        pub fn seek_bits(&mut self, bit_pos: usize) -> Result<()> {
+            self.reader.seek(bit_pos >> 3)?;
+            self.buffer_start = (bit_pos >> 3) as u64;
+            self.cap = 0;
+            self.pos = 0;
+            self.bit_pos = (bit_pos & 7) as u8;
+            Ok(())
+        }
+
+        pub fn read_bits(&mut self, num_bits: u8) -> Result<u64> {
+            if num_bits > 64 {
+                return Err(Error::NotSupported);
+            }
+            let mut result = 0u64;
+            let mut bits_left = num_bits;
+            while bits_left > 0 {
+                if self.pos >= self.cap {
+                    self.fill_buf()?;
+                    if self.cap == 0 {
+                        return Err(Error::NotSupported);
+                    }
+                }
+                if self.bit_pos == 0 && bits_left >= 8 {
+                    let can_take = ((bits_left / 8) as usize).min(self.cap - self.pos);
+                    for &b in &self.buffer[self.pos..self.pos + can_take] {
+                        result = (result << 8) | b as u64;
+                    }
+                    self.pos += can_take;
+                    bits_left -= (can_take * 8) as u8;
+                    continue;
+                }
+                let available_in_byte = 8 - self.bit_pos;
+                let take = bits_left.min(available_in_byte);
+                let shift = available_in_byte - take;
+                let mask = (0xFF >> (8 - take)) << shift;
+                let val = (self.buffer[self.pos] & mask) >> shift;
+                result = (result << take) | val as u64;
+                bits_left -= take;
+                self.bit_pos += take;
+                if self.bit_pos == 8 {
+                    self.bit_pos = 0;
+                    self.pos += 1;
+                }
+            }
+            Ok(result)
+        }
+    }
+}
+
+fn test_data() -> Vec<u8> {
+    (0..DATA_LEN)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect()
+}
+
+/// Field widths of a typical unaligned record layout.
+const FIELDS: [u8; 8] = [1, 3, 5, 7, 12, 13, 17, 6];
+
+fn bench_read_bits(c: &mut Criterion) {
+    let data = test_data();
+    let fields_bits: u64 = FIELDS.iter().map(|&b| b as u64).sum();
+    let records = (DATA_LEN as u64 * 8) / fields_bits;
+
+    let mut group = c.benchmark_group("read_bits");
+    group.throughput(Throughput::Bytes(DATA_LEN as u64));
+
+    group.bench_function("cached/mixed", |b| {
+        b.iter(|| {
+            let mut reader = BitReader::new(BinMemoryBuffer::from(data.as_slice()));
+            let mut sum = 0u64;
+            for _ in 0..records {
+                for &bits in FIELDS.iter() {
+                    sum = sum.wrapping_add(reader.read_bits(bits).unwrap());
+                }
+            }
+            black_box(sum)
+        })
+    });
+    group.bench_function("legacy/mixed", |b| {
+        b.iter(|| {
+            let mut reader = legacy::BitReader::new(BinMemoryBuffer::from(data.as_slice()));
+            let mut sum = 0u64;
+            for _ in 0..records {
+                for &bits in FIELDS.iter() {
+                    sum = sum.wrapping_add(reader.read_bits(bits).unwrap());
+                }
+            }
+            black_box(sum)
+        })
+    });
+
+    group.bench_function("cached/single_bit", |b| {
+        b.iter(|| {
+            let mut reader = BitReader::new(BinMemoryBuffer::from(data.as_slice()));
+            let mut sum = 0u64;
+            for _ in 0..DATA_LEN * 8 {
+                sum += reader.read_bits(1).unwrap();
+            }
+            black_box(sum)
+        })
+    });
+    group.bench_function("legacy/single_bit", |b| {
+        b.iter(|| {
+            let mut reader = legacy::BitReader::new(BinMemoryBuffer::from(data.as_slice()));
+            let mut sum = 0u64;
+            for _ in 0..DATA_LEN * 8 {
+                sum += reader.read_bits(1).unwrap();
+            }
+            black_box(sum)
+        })
+    });
+    group.finish();
+}
+
+fn bench_seek_bits(c: &mut Criterion) {
+    let data = test_data();
+    let mut group = c.benchmark_group("seek_bits");
+
+    // Short backward jumps that stay inside the reader's buffer.
+    group.bench_function("cached/in_buffer", |b| {
+        let mut reader = BitReader::new(BinMemoryBuffer::from(data.as_slice()));
+        b.iter(|| {
+            let mut sum = 0u64;
+            for i in 0..1024usize {
+                reader.seek_bits((i * 37) % 60_000).unwrap();
+                sum = sum.wrapping_add(reader.read_bits(24).unwrap());
+            }
+            black_box(sum)
+        })
+    });
+    group.bench_function("legacy/in_buffer", |b| {
+        let mut reader = legacy::BitReader::new(BinMemoryBuffer::from(data.as_slice()));
+        b.iter(|| {
+            let mut sum = 0u64;
+            for i in 0..1024usize {
+                reader.seek_bits((i * 37) % 60_000).unwrap();
+                sum = sum.wrapping_add(reader.read_bits(24).unwrap());
+            }
+            black_box(sum)
+        })
+    });
+    group.finish();
+}
+
+fn bench_find_pattern(c: &mut Criterion) {
+    let data = test_data();
+    let mut group = c.benchmark_group("find_pattern");
+    group.throughput(Throughput::Bytes(DATA_LEN as u64));
+
+    // A 32 bit sync word that does not occur, so the whole capture is scanned.
+    group.bench_function("exact", |b| {
+        b.iter(|| {
+            let mut reader = BitReader::new(BinMemoryBuffer::from(data.as_slice()));
+            black_box(reader.find_pattern(0x1ACF_FC1D, 32, u64::MAX).unwrap())
+        })
+    });
+    group.bench_function("tolerance_2", |b| {
+        b.iter(|| {
+            let mut reader = BitReader::new(BinMemoryBuffer::from(data.as_slice()));
+            black_box(
+                reader
+                    .find_pattern_with_tolerance(0x1ACF_FC1D, 32, u64::MAX, 2)
+                    .unwrap(),
+            )
+        })
+    });
+    group.finish();
+}
+
+fn bench_read_packed(c: &mut Criterion) {
+    let data = test_data();
+    let mut group = c.benchmark_group("read_packed");
+    group.throughput(Throughput::Bytes(DATA_LEN as u64));
+
+    for bits in [10u8, 12] {
+        // A whole number of MIPI groups
+        let count = DATA_LEN * 8 / bits as usize / 4 * 4;
+        let mut out = vec![0u16; count];
+        group.bench_function(format!("read_bits/{}", bits), |b| {
+            b.iter(|| {
+                let mut reader = BitReader::new(BinMemoryBuffer::from(data.as_slice()));
+                for sample in out.iter_mut() {
+                    *sample = reader.read_bits(bits).unwrap() as u16;
+                }
+                black_box(out[count - 1])
+            })
+        });
+        for (name, order) in [("msb", BitOrder::MsbFirst), ("mipi", BitOrder::Mipi)] {
+            group.bench_function(format!("packed_{}/{}", name, bits), |b| {
+                b.iter(|| {
+                    let mut reader = BitReader::new(BinMemoryBuffer::from(data.as_slice()));
+                    reader.read_packed_into(&mut out, bits, order).unwrap();
+                    black_box(out[count - 1])
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_read_bits,
+    bench_seek_bits,
+    bench_find_pattern,
+    bench_read_packed
+);
+criterion_main!(benches);
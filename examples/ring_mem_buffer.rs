@@ -1,23 +1,30 @@
 use std::io::Write;
 
-use binary_data::{BigEndian, BinRingMemoryBuffer, ReadBytes, WriteBytes};
+use binary_data::{BigEndian, BinRingMemoryBuffer, OverflowPolicy, ReadBytes, WriteBytes};
 
 fn main() {
     // Read
     let buff = vec![1, 2, 3, 4, 5];
-    let mut cyclic_mem_buff = BinRingMemoryBuffer::from(buff);
+    let mut cyclic_mem_buff = BinRingMemoryBuffer::new_with_offset(buff, 3);
     let ret = cyclic_mem_buff.read_u16::<BigEndian>();
     println!("{:?}", ret);
     let ret = cyclic_mem_buff.read_u8();
     println!("{:?}", ret);
-    let ret = cyclic_mem_buff.read_into_vec(4);
+    let ret = cyclic_mem_buff.read_into_vec(2);
     println!("{:?}", ret);
 
     // Write
-    let buff = vec![0, 0, 0, 0, 0];
-    let mut cyclic_mem_buff = BinRingMemoryBuffer::from(buff);
+    let mut cyclic_mem_buff = BinRingMemoryBuffer::with_capacity(5);
     let ret = cyclic_mem_buff.write(&[2, 2, 2, 3, 3, 3, 4, 5]);
-    println!("{:?}. {:?}", ret, cyclic_mem_buff);
+    println!("{:?}. {:?}", ret, cyclic_mem_buff.as_slices());
     let ret = cyclic_mem_buff.write_u8(8);
-    println!("{:?}. {:?}", ret, cyclic_mem_buff);
+    println!("{:?}. {:?}", ret, cyclic_mem_buff.as_slices());
+
+    // FIFO which refuses to overwrite unread data
+    let mut fifo =
+        BinRingMemoryBuffer::with_capacity(4).with_overflow_policy(OverflowPolicy::Block);
+    let ret = fifo.write(&[1, 2, 3, 4, 5, 6]);
+    println!("{:?}. {:?}", ret, fifo.as_slices());
+    let ret = fifo.read_u8();
+    println!("{:?}. free {}", ret, fifo.free());
 }
@@ -0,0 +1,175 @@
+use std::{
+    fs,
+    io::{self, ErrorKind, Read, Write},
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    bin_error::{Error, Result},
+    BinFile, BinSeek,
+};
+
+/// Number of temp file names tried before giving up.
+const MAX_ATTEMPTS: usize = 100;
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// AtomicBinFile writes a file through a temporary file in the same directory, which replaces
+/// the target only on `commit`. Created by `BinWriter::create_atomic`.
+///
+/// The guard dereferences to the `BinFile` of the temporary file. Dropping it without `commit`
+/// deletes the temporary file and leaves the target untouched, so a crash or an error while
+/// writing never leaves a partly written target behind. The target is either the old or the
+/// complete new file.
+#[derive(Debug)]
+pub struct AtomicBinFile {
+    file: Option<BinFile>,
+    temp_path: PathBuf,
+    target: PathBuf,
+}
+
+impl AtomicBinFile {
+    pub(crate) fn new(target: &Path) -> Result<Self> {
+        let dir = match target.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let name = target
+            .file_name()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "path does not name a file"))?;
+
+        let mut attempt = 0;
+        let (file, temp_path) = loop {
+            let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let mut temp_name = std::ffi::OsString::from(".");
+            temp_name.push(name);
+            temp_name.push(format!(".{}.{}.tmp", process::id(), counter));
+            let temp_path = dir.join(temp_name);
+            match BinFile::options()
+                .read(true)
+                .write(true)
+                .create_new(true)
+                .open(&temp_path)
+            {
+                Ok(file) => break (file, temp_path),
+                Err(Error::File(err))
+                    if err.kind() == ErrorKind::AlreadyExists && attempt < MAX_ATTEMPTS =>
+                {
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        };
+
+        let atomic = Self {
+            file: Some(file),
+            temp_path,
+            target: target.to_path_buf(),
+        };
+        // A replaced file keeps its permissions
+        if let Ok(metadata) = fs::metadata(target) {
+            fs::set_permissions(&atomic.temp_path, metadata.permissions())?;
+        }
+        Ok(atomic)
+    }
+
+    /// Returns the path the file is moved to on `commit`
+    pub fn target_path(&self) -> &Path {
+        &self.target
+    }
+
+    /// Returns the path of the temporary file which is written
+    pub fn temp_path(&self) -> &Path {
+        &self.temp_path
+    }
+
+    /// Switches the temporary file to buffered access, see `BinFile::buffered`
+    pub fn buffered(mut self, capacity: usize) -> Result<Self> {
+        if let Some(file) = self.file.take() {
+            self.file = Some(file.buffered(capacity)?);
+        }
+        Ok(self)
+    }
+
+    /// Flushes and syncs the temporary file and renames it over the target.
+    ///
+    /// If flushing, syncing or the rename fails, the temporary file is deleted and the target
+    /// is left as it was. On unix the directory is synced after the rename, if that fails the
+    /// target is already replaced by the new file but the replacement may not survive a crash.
+    pub fn commit(mut self) -> Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.sync_all()?;
+        }
+        fs::rename(&self.temp_path, &self.target)?;
+        // The temporary file is the target now, it must not be removed on drop
+        self.temp_path.clear();
+        // The rename is durable once the directory itself is synced
+        #[cfg(unix)]
+        if let Some(dir) = self.target.parent() {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            fs::File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+impl Deref for AtomicBinFile {
+    type Target = BinFile;
+
+    fn deref(&self) -> &BinFile {
+        self.file.as_ref().expect("file is present until commit")
+    }
+}
+
+impl DerefMut for AtomicBinFile {
+    fn deref_mut(&mut self) -> &mut BinFile {
+        self.file.as_mut().expect("file is present until commit")
+    }
+}
+
+impl Read for AtomicBinFile {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.deref_mut().read(buffer)
+    }
+}
+
+impl Write for AtomicBinFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.deref_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.deref_mut().flush()
+    }
+}
+
+impl BinSeek for AtomicBinFile {
+    fn seek(&mut self, to: usize) -> Result<usize> {
+        self.deref_mut().seek(to)
+    }
+
+    fn pos(&mut self) -> Result<usize> {
+        self.deref_mut().pos()
+    }
+
+    fn len(&self) -> Result<usize> {
+        self.deref().len()
+    }
+}
+
+impl Drop for AtomicBinFile {
+    fn drop(&mut self) {
+        // Close the file before deleting it, pending buffered data does not matter anymore
+        drop(self.file.take());
+        if !self.temp_path.as_os_str().is_empty() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
@@ -1,20 +1,42 @@
+use crate::bin_bitset::{BitSet, Flag, FlagSet};
+use crate::bin_checkpoint::BitCheckpoint;
 use crate::bin_error::{Error, Result};
+use crate::bin_packed::{self as packed, BitOrder};
 use crate::bin_seek::BinSeek;
-use std::io::Read;
+use crate::bin_stream::BinStream;
+use std::io::{self, ErrorKind, Read};
 
 const DEFAULT_BUF_SIZE: usize = 8 * 1024;
 
+/// Size of the byte chunks packed samples are unpacked from.
+const PACKED_CHUNK: usize = 4 * 1024;
+
+/// The largest number of bits that is guaranteed to be in the cache after a successful refill.
+const MAX_CACHE_READ: u32 = 56;
+
+/// The most bytes the bits in the cache can come from, kept in the buffer when it is refilled.
+const CACHE_BYTES: usize = 8;
+
 /// A buffered reader for bit-level data that allows seeking to arbitrary bit positions.
+///
+/// Bits are consumed from a 64-bit cache which is refilled from the internal buffer
+/// with a single unaligned big-endian load whenever at least 8 bytes are available.
+///
+/// Over a source which is not seekable (see `BitReader::forward_only`) the reader only moves
+/// forward: seeking backwards past the internal buffer and `read_bit_at` outside of it
+/// return `Error::NotSupported`.
 pub struct BitReader<R: Read + BinSeek> {
     reader: R,
     buffer: Vec<u8>,
-    // The byte position in the buffer.
+    // The position in the buffer of the next byte to be loaded into the cache.
     pos: usize,
     // The number of bytes read into the buffer.
     cap: usize,
-    // The bit position within the current byte (the byte at self.buffer[self.pos]).
-    // A value of 8 means the byte has been fully read.
-    bit_pos: u8,
+    // MSB-aligned bit cache, the top `cache_bits` bits are the next bits of the stream.
+    // Bits below `cache_bits` are either zero or already hold the following stream bits.
+    cache: u64,
+    // The number of valid bits in the cache.
+    cache_bits: u32,
     // The byte position in the underlying reader where the current buffer starts.
     buffer_start: u64,
 }
@@ -30,76 +52,328 @@ impl<R: Read + BinSeek> BitReader<R> {
         let buffer_start = reader.pos().unwrap_or(0) as u64;
         Self {
             reader,
-            buffer: vec![0; capacity],
+            buffer: vec![0; capacity.max(1) + CACHE_BYTES],
             pos: 0,
             cap: 0,
-            bit_pos: 0,
+            cache: 0,
+            cache_bits: 0,
             buffer_start,
         }
     }
 
-    /// Fills the internal buffer from the underlying reader.
+    /// Consumes the `BitReader` and returns the underlying reader.
+    /// Bytes read ahead into the internal buffer are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Fills the internal buffer from the underlying reader, no new bytes were read if `pos`
+    /// is still at `cap` afterwards.
+    /// Must only be called once every buffered byte has been moved into the cache.
+    ///
+    /// The bytes the cached bits come from are kept at the start of the buffer, so seeking
+    /// back to any position the cache still holds does not need the underlying reader.
     #[inline]
     fn fill_buf(&mut self) -> Result<()> {
-        if self.pos < self.cap {
-            // If the buffer wasn't fully consumed, we must seek the underlying 
-            // reader back to our logical position before refilling.
-            self.reader.seek((self.buffer_start + self.pos as u64) as usize)?;
-        }
-        self.buffer_start = self.reader.pos()? as u64;
-        self.cap = self.reader.read(&mut self.buffer)?;
-        self.pos = 0;
+        debug_assert!(self.pos >= self.cap);
+        let keep = (self.cache_bits.div_ceil(8) as usize).min(self.cap);
+        self.buffer.copy_within(self.cap - keep..self.cap, 0);
+        // The underlying reader is always positioned right after the buffered bytes.
+        self.buffer_start += (self.cap - keep) as u64;
+        self.pos = keep;
+        self.cap = keep;
+        let len = loop {
+            match self.reader.read(&mut self.buffer[keep..]) {
+                Ok(len) => break len,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        };
+        self.cap += len;
         Ok(())
     }
 
+    /// Tops up the bit cache so it holds at least 56 bits, unless the stream ends first.
+    #[inline(always)]
+    fn refill(&mut self) -> Result<()> {
+        if self.pos + 8 <= self.cap {
+            // Fast path: one unaligned load. Byte `pos` starts exactly at bit `cache_bits`
+            // of the cache, so the load can be OR-ed in as is.
+            let word = load_be_u64(&self.buffer[self.pos..]);
+            self.cache |= word >> self.cache_bits;
+            self.pos += ((63 - self.cache_bits) >> 3) as usize;
+            self.cache_bits |= MAX_CACHE_READ;
+            return Ok(());
+        }
+        self.refill_slow()
+    }
+
+    /// Byte by byte refill, used close to the end of the buffer.
+    #[cold]
+    fn refill_slow(&mut self) -> Result<()> {
+        while self.cache_bits <= MAX_CACHE_READ {
+            if self.pos >= self.cap {
+                self.fill_buf()?;
+                if self.pos >= self.cap {
+                    break;
+                }
+            }
+            self.cache |= (self.buffer[self.pos] as u64) << (MAX_CACHE_READ - self.cache_bits);
+            self.pos += 1;
+            self.cache_bits += 8;
+        }
+        Ok(())
+    }
+
+    /// Makes sure that at least `num_bits` (up to 56) are in the cache.
+    #[inline(always)]
+    fn ensure_bits(&mut self, num_bits: u32) -> Result<()> {
+        if self.cache_bits < num_bits {
+            self.refill()?;
+            if self.cache_bits < num_bits {
+                return Err(Error::File(std::io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "end of file",
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops `num_bits` (less than 64) from the cache.
+    #[inline(always)]
+    fn consume(&mut self, num_bits: u32) {
+        debug_assert!(num_bits < 64 && num_bits <= self.cache_bits);
+        self.cache <<= num_bits;
+        self.cache_bits -= num_bits;
+    }
+
     /// Returns the absolute bit position in the stream
     #[inline]
     pub fn bit_cursor(&self) -> u64 {
-        (self.buffer_start + self.pos as u64) * 8 + self.bit_pos as u64
+        (self.buffer_start + self.pos as u64) * 8 - self.cache_bits as u64
     }
 
     /// Seeks to an arbitrary bit position in the stream.
-    /// This will invalidate the internal buffer.
+    ///
+    /// Targets held in the bit cache or the internal buffer are reached without the underlying
+    /// reader, so the buffer is kept and this works on sources which are not seekable too.
+    /// Other targets seek the underlying reader and invalidate the buffer.
+    ///
+    /// When a source which is not seekable ends before a forward target, the data in between
+    /// is already skipped: the seek fails and the position is left at the end of the stream.
+    /// A backward seek which is not supported fails and leaves the position unchanged.
     pub fn seek_bits(&mut self, bit_pos: usize) -> Result<()> {
-        let byte_pos = bit_pos >> 3;
-        let bit_offset = (bit_pos & 7) as u8;
+        let target = bit_pos as u64;
+        let cursor = self.bit_cursor();
+        if target >= cursor && target <= cursor + self.cache_bits as u64 {
+            // Forward inside the cache, drop the bits in between
+            let skip = (target - cursor) as u32;
+            if skip == self.cache_bits {
+                self.cache = 0;
+                self.cache_bits = 0;
+            } else {
+                self.consume(skip);
+            }
+            return Ok(());
+        }
 
-        self.reader.seek(byte_pos)?;
-        self.buffer_start = byte_pos as u64;
-        self.cap = 0;
-        self.pos = 0;
-        self.bit_pos = bit_offset;
+        let byte_pos = target >> 3;
+        let bit_offset = (target & 7) as u32;
+        let buffer_end = self.buffer_start + self.cap as u64;
 
+        // The reader is positioned at the end of the buffer, so that is a local target too
+        if byte_pos >= self.buffer_start && byte_pos <= buffer_end {
+            self.pos = (byte_pos - self.buffer_start) as usize;
+        } else {
+            if let Err(err) = self.reader.seek(byte_pos as usize) {
+                if byte_pos > buffer_end && !self.reader.is_seekable() {
+                    // Continue from wherever the stream stopped.
+                    self.buffer_start = self.reader.pos()? as u64;
+                    self.cap = 0;
+                    self.pos = 0;
+                    self.cache = 0;
+                    self.cache_bits = 0;
+                }
+                return Err(err);
+            }
+            self.buffer_start = byte_pos;
+            self.cap = 0;
+            self.pos = 0;
+        }
+        self.cache = 0;
+        self.cache_bits = 0;
+
+        if bit_offset > 0 {
+            self.ensure_bits(bit_offset)?;
+            self.consume(bit_offset);
+        }
         Ok(())
     }
 
-    /// Reads a single bit from the current position.
+    /// Returns true if the current position is on a byte boundary.
     #[inline]
-    pub fn read_bit(&mut self) -> Result<bool> {
-        // Ensure a byte is available
-        if self.pos >= self.cap {
-            self.fill_buf()?;
-            if self.cap == 0 {
-                return Err(Error::File(std::io::Error::new(
-                    std::io::ErrorKind::UnexpectedEof,
-                    "end of file",
-                )));
+    pub fn is_byte_aligned(&self) -> bool {
+        self.cache_bits & 7 == 0
+    }
+
+    /// Skips the remaining bits of the current byte, if any.
+    pub fn byte_align(&mut self) {
+        self.consume(self.cache_bits & 7);
+    }
+
+    /// Remember the current bit position, the returned guard rewinds to it on drop unless committed.
+    /// Rewinding past the internal buffer needs a seekable source.
+    pub fn checkpoint(&mut self) -> BitCheckpoint<'_, R> {
+        BitCheckpoint::new(self)
+    }
+
+    /// Skips `num_bits` bits forward from the current position.
+    /// Fails like `seek_bits` if the stream ends first.
+    pub fn skip_bits(&mut self, num_bits: usize) -> Result<()> {
+        self.seek_bits(self.bit_cursor() as usize + num_bits)
+    }
+
+    /// Returns the next `num_bits` (up to 56) without consuming them.
+    #[inline]
+    pub fn peek_bits(&mut self, num_bits: u8) -> Result<u64> {
+        if num_bits == 0 {
+            return Ok(0);
+        }
+        if num_bits as u32 > MAX_CACHE_READ {
+            return Err(Error::NotSupported);
+        }
+        self.ensure_bits(num_bits as u32)?;
+        Ok(self.cache >> (64 - num_bits as u32))
+    }
+
+    /// Returns the next `num_bits` (1 to 56) without consuming them, padded with zero bits
+    /// past the end of the stream, together with the number of bits actually available.
+    #[inline]
+    pub(crate) fn peek_bits_padded(&mut self, num_bits: u32) -> Result<(u64, u32)> {
+        debug_assert!(num_bits > 0 && num_bits <= MAX_CACHE_READ);
+        if self.cache_bits < num_bits {
+            self.refill()?;
+        }
+        let available = self.cache_bits.min(num_bits);
+        let bits = (self.cache & mask_high(available)) >> (64 - num_bits);
+        Ok((bits, available))
+    }
+
+    /// Consumes `num_bits` which were checked to be available with a peek.
+    #[inline]
+    pub(crate) fn consume_bits(&mut self, num_bits: u32) {
+        self.consume(num_bits);
+    }
+
+    /// Reads `out.len()` samples of `bits_per_sample` (1 to 16) bits packed back to back.
+    ///
+    /// `MsbFirst` works from any bit position and leaves the cursor right after the last sample.
+    /// `LsbFirst` and `Mipi` need a byte aligned reader and consume whole bytes, `Mipi` also
+    /// needs a whole number of groups (4 samples for RAW10/RAW14, 2 for RAW12).
+    pub fn read_packed_into(
+        &mut self,
+        out: &mut [u16],
+        bits_per_sample: u8,
+        order: BitOrder,
+    ) -> Result<()> {
+        let (group_samples, group_bytes) = order.group(bits_per_sample)?;
+        if order == BitOrder::Mipi && !out.len().is_multiple_of(group_samples) {
+            return Err(Error::NotSupported);
+        }
+        if !self.is_byte_aligned() {
+            if order != BitOrder::MsbFirst {
+                return Err(Error::NotSupported);
+            }
+            for sample in out.iter_mut() {
+                *sample = self.read_bits(bits_per_sample)? as u16;
             }
+            return Ok(());
         }
 
-        debug_assert!(self.bit_pos < 8);
+        // Whole groups are unpacked from bytes in chunks.
+        let mut scratch = [0u8; PACKED_CHUNK];
+        let chunk_samples = PACKED_CHUNK / group_bytes * group_samples;
+        let whole = out.len() / group_samples * group_samples;
+        let (groups, rest) = out.split_at_mut(whole);
+        for chunk in groups.chunks_mut(chunk_samples) {
+            let bytes = &mut scratch[..chunk.len() / group_samples * group_bytes];
+            self.read_exact(bytes)?;
+            packed::unpack(bytes, chunk, bits_per_sample, order);
+        }
 
-        let byte = self.buffer[self.pos];
-        let bit = (byte >> (7 - self.bit_pos)) & 1;
+        if order == BitOrder::MsbFirst {
+            for sample in rest.iter_mut() {
+                *sample = self.read_bits(bits_per_sample)? as u16;
+            }
+        } else if !rest.is_empty() {
+            let bytes = &mut scratch[..packed::packed_len(rest.len(), bits_per_sample)];
+            self.read_exact(bytes)?;
+            packed::unpack(bytes, rest, bits_per_sample, order);
+        }
+        Ok(())
+    }
 
-        // Advance state
-        if self.bit_pos == 7 {
-            self.bit_pos = 0;
-            self.pos += 1;
-        } else {
-            self.bit_pos += 1;
+    /// Scans forward bit by bit for the `len` (1 to 48) bit `pattern`, testing at most
+    /// `max_search` start positions. On a match the cursor is left on its first bit and the
+    /// position is returned, otherwise the cursor is left after the last position tested.
+    pub fn find_pattern(&mut self, pattern: u64, len: u8, max_search: u64) -> Result<Option<u64>> {
+        self.find_pattern_with_tolerance(pattern, len, max_search, 0)
+    }
+
+    /// Same as `find_pattern`, but a match may differ from `pattern` in up to `max_errors` bits.
+    pub fn find_pattern_with_tolerance(
+        &mut self,
+        pattern: u64,
+        len: u8,
+        max_search: u64,
+        max_errors: u32,
+    ) -> Result<Option<u64>> {
+        let len = len as u32;
+        if len == 0 || len > MAX_CACHE_READ - 8 {
+            return Err(Error::NotSupported);
         }
+        let mask = (1u64 << len) - 1;
+        let pattern = pattern & mask;
 
+        let mut remaining = max_search;
+        while remaining > 0 {
+            if self.cache_bits < MAX_CACHE_READ {
+                self.refill()?;
+            }
+            if self.cache_bits < len {
+                // Not enough data left for another match.
+                return Ok(None);
+            }
+            // Test every start position inside the cached window.
+            let window = self.cache;
+            let offsets = (self.cache_bits - len + 1).min(MAX_CACHE_READ) as u64;
+            let offsets = offsets.min(remaining);
+            let found = if max_errors == 0 {
+                (0..offsets as u32)
+                    .find(|&offset| (window >> (64 - len - offset)) & mask == pattern)
+            } else {
+                (0..offsets as u32).find(|&offset| {
+                    let candidate = (window >> (64 - len - offset)) & mask;
+                    (candidate ^ pattern).count_ones() <= max_errors
+                })
+            };
+            if let Some(offset) = found {
+                self.consume(offset);
+                return Ok(Some(self.bit_cursor()));
+            }
+            self.consume(offsets as u32);
+            remaining -= offsets;
+        }
+        Ok(None)
+    }
+
+    /// Reads a single bit from the current position.
+    #[inline]
+    pub fn read_bit(&mut self) -> Result<bool> {
+        self.ensure_bits(1)?;
+        let bit = self.cache >> 63;
+        self.consume(1);
         Ok(bit != 0)
     }
 
@@ -117,57 +391,48 @@ impl<R: Read + BinSeek> BitReader<R> {
         if num_bits > 64 {
             return Err(Error::NotSupported);
         }
-
-        let mut result = 0u64;
-        let mut bits_left = num_bits;
-
-        while bits_left > 0 {
-            if self.pos >= self.cap {
+        let num_bits = num_bits as u32;
+        if num_bits > MAX_CACHE_READ {
+            // Check that the whole value is there before consuming any of it, so a failed
+            // read leaves the position unchanged. Every buffered byte is in the cache once
+            // `pos` reaches `cap`, so the buffer can be refilled without losing bits.
+            self.ensure_bits(MAX_CACHE_READ)?;
+            if self.cache_bits < num_bits && self.pos >= self.cap {
                 self.fill_buf()?;
-                if self.cap == 0 {
+                if self.pos >= self.cap {
                     return Err(Error::File(std::io::Error::new(
-                        std::io::ErrorKind::UnexpectedEof,
+                        ErrorKind::UnexpectedEof,
                         "end of file",
                     )));
                 }
             }
+            let high = self.read_bits((num_bits - 32) as u8)?;
+            let low = self.read_bits(32)?;
+            return Ok((high << 32) | low);
+        }
 
-            // Optimization for byte-aligned multi-byte reads
-            if self.bit_pos == 0 && bits_left >= 8 {
-                let take_bytes = (bits_left / 8) as usize;
-                let available_bytes = self.cap - self.pos;
-                let can_take = take_bytes.min(available_bytes);
-
-                if can_take > 0 {
-                    let slice = &self.buffer[self.pos..self.pos + can_take];
-                    for &b in slice {
-                        result = (result << 8) | b as u64;
-                    }
-                    self.pos += can_take;
-                    bits_left -= (can_take * 8) as u8;
-                    continue;
-                }
-            }
-
-            let available_in_byte = 8 - self.bit_pos;
-            let take = bits_left.min(available_in_byte);
-
-            let byte = self.buffer[self.pos];
-            let shift = available_in_byte - take;
-            let mask = (0xFF >> (8 - take)) << shift;
-            let val = (byte & mask) >> shift;
-
-            result = (result << take) | val as u64;
-            bits_left -= take;
-            self.bit_pos += take;
+        self.ensure_bits(num_bits)?;
+        let result = self.cache >> (64 - num_bits);
+        self.consume(num_bits);
+        Ok(result)
+    }
 
-            if self.bit_pos == 8 {
-                self.bit_pos = 0;
-                self.pos += 1;
-            }
+    /// Reads a bitmap of `num_bits` bits. The last bit read becomes bit 0 of the set,
+    /// as it would be the least significant bit of `read_bits(num_bits)`.
+    pub fn read_bitset(&mut self, num_bits: usize) -> Result<BitSet> {
+        let mut set = BitSet::new(num_bits);
+        let mut remaining = num_bits;
+        for word in set.words_mut().iter_mut().rev() {
+            let bits = remaining - (remaining - 1) / 64 * 64;
+            *word = self.read_bits(bits as u8)?;
+            remaining -= bits;
         }
+        Ok(set)
+    }
 
-        Ok(result)
+    /// Reads a bitmap of `num_bits` bits as a set of typed flags, see `read_bitset`.
+    pub fn read_flags<F: Flag>(&mut self, num_bits: usize) -> Result<FlagSet<F>> {
+        self.read_bitset(num_bits).map(FlagSet::new)
     }
 
     /// Reads a single bit from a specific position without altering the reader's main position.
@@ -187,6 +452,9 @@ impl<R: Read + BinSeek> BitReader<R> {
         }
 
         // Slow path: temporary seek
+        if !self.reader.is_seekable() {
+            return Err(Error::NotSupported);
+        }
         let byte_pos = bit_pos >> 3;
         let bit_offset = (bit_pos & 7) as u8;
 
@@ -194,8 +462,9 @@ impl<R: Read + BinSeek> BitReader<R> {
 
         self.reader.seek(byte_pos)?;
         let mut byte = [0u8; 1];
-        self.reader.read_exact(&mut byte)?;
+        let result = self.reader.read_exact(&mut byte);
         self.reader.seek(saved_pos)?;
+        result?;
 
         Ok(((byte[0] >> (7 - bit_offset)) & 1) != 0)
     }
@@ -207,3 +476,84 @@ impl<R: Read + BinSeek> BitReader<R> {
             .map(|b| if b { 1.into() } else { 0.into() })
     }
 }
+
+/// Implement the `Read` trait for `BitReader`, so bytes (and with `ReadBytes` multi-byte values
+/// in either byte order) can be read starting at any bit position.
+impl<R: Read + BinSeek> Read for BitReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        let mut len = 0;
+        // Hand out the whole bytes which are already in the cache.
+        while self.cache_bits >= 8 && len < buffer.len() {
+            buffer[len] = (self.cache >> 56) as u8;
+            self.consume(8);
+            len += 1;
+        }
+        if len == buffer.len() {
+            return Ok(len);
+        }
+
+        if self.cache_bits == 0 {
+            // Fast path: byte aligned, copy straight from the buffer.
+            // The look-ahead bits would no longer match once `pos` moves.
+            self.cache = 0;
+            if self.pos >= self.cap {
+                if buffer.len() - len >= self.buffer.len() {
+                    // Large reads bypass the internal buffer.
+                    self.buffer_start += self.cap as u64;
+                    self.pos = 0;
+                    self.cap = 0;
+                    let read = self.reader.read(&mut buffer[len..])?;
+                    self.buffer_start += read as u64;
+                    return Ok(len + read);
+                }
+                self.fill_buf()?;
+            }
+            let take = (self.cap - self.pos).min(buffer.len() - len);
+            buffer[len..len + take].copy_from_slice(&self.buffer[self.pos..self.pos + take]);
+            self.pos += take;
+            return Ok(len + take);
+        }
+
+        // Unaligned: shift whole bytes out of the cache, up to 7 per refill.
+        while len < buffer.len() {
+            self.refill()?;
+            if self.cache_bits < 8 {
+                break;
+            }
+            while self.cache_bits >= 8 && len < buffer.len() {
+                buffer[len] = (self.cache >> 56) as u8;
+                self.consume(8);
+                len += 1;
+            }
+        }
+        Ok(len)
+    }
+}
+
+impl<R: Read> BitReader<BinStream<R>> {
+    /// Creates a new forward-only `BitReader` over a source that only implements `Read`.
+    pub fn forward_only(reader: R) -> Self {
+        Self::new(BinStream::new(reader))
+    }
+}
+
+/// Returns a mask with the top `num_bits` (up to 64) bits set.
+#[inline(always)]
+fn mask_high(num_bits: u32) -> u64 {
+    if num_bits == 0 {
+        0
+    } else {
+        u64::MAX << (64 - num_bits)
+    }
+}
+
+/// Loads the first 8 bytes of `bytes` as a big-endian `u64`.
+#[inline(always)]
+fn load_be_u64(bytes: &[u8]) -> u64 {
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&bytes[..8]);
+    u64::from_be_bytes(word)
+}
@@ -0,0 +1,197 @@
+use std::io::{self, Read, Write};
+
+use crate::{
+    bin_error::{Error, Result},
+    BinSeek, BitReader, BitWriter,
+};
+
+/// The number of equal bits after which a stuff bit is inserted.
+const MAX_RUN: u8 = 5;
+
+/// The bit stuffing scheme of a link layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StuffingRule {
+    /// HDLC: a zero is inserted after five consecutive ones.
+    Hdlc,
+    /// CAN: a complementary bit is inserted after five consecutive equal bits.
+    /// The stuff bit counts towards the next run.
+    Can,
+}
+
+/// Tracks the run of equal bits shared by stuffing and destuffing.
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    rule: StuffingRule,
+    bit: bool,
+    len: u8,
+}
+
+impl Run {
+    fn new(rule: StuffingRule) -> Self {
+        Self {
+            rule,
+            bit: false,
+            len: 0,
+        }
+    }
+
+    /// Adds a bit of the stuffed stream to the run.
+    fn push(&mut self, bit: bool) {
+        match self.rule {
+            StuffingRule::Hdlc => self.len = if bit { self.len + 1 } else { 0 },
+            StuffingRule::Can if bit == self.bit => self.len += 1,
+            StuffingRule::Can => {
+                self.bit = bit;
+                self.len = 1;
+            }
+        }
+    }
+
+    /// Returns the bit which must come next, if the run requires a stuff bit.
+    fn stuff_bit(&self) -> Option<bool> {
+        if self.len < MAX_RUN {
+            return None;
+        }
+        match self.rule {
+            StuffingRule::Hdlc => Some(false),
+            StuffingRule::Can => Some(!self.bit),
+        }
+    }
+}
+
+/// BitDestuffer removes stuff bits from a raw bit stream read through a `BitReader`.
+///
+/// A stuff bit which does not have the expected value is reported as
+/// `Error::StuffingViolation` with its raw bit offset. The adapter also implements `Read`,
+/// packing the destuffed bits into bytes, so it can be wrapped by another `BitReader`.
+pub struct BitDestuffer<R: Read + BinSeek> {
+    reader: BitReader<R>,
+    run: Run,
+}
+
+impl<R: Read + BinSeek> BitDestuffer<R> {
+    pub fn new(reader: BitReader<R>, rule: StuffingRule) -> Self {
+        Self {
+            reader,
+            run: Run::new(rule),
+        }
+    }
+
+    /// Returns the position of the next raw bit in the underlying stream
+    pub fn raw_bit_offset(&self) -> u64 {
+        self.reader.bit_cursor()
+    }
+
+    /// Forgets the current run of bits, e.g. at the start of a new frame.
+    pub fn reset(&mut self) {
+        self.run = Run::new(self.run.rule);
+    }
+
+    /// Returns a mutable reference to the raw `BitReader`, e.g. to look for frame delimiters.
+    /// Call `reset` after reading raw bits.
+    pub fn get_mut(&mut self) -> &mut BitReader<R> {
+        &mut self.reader
+    }
+
+    /// Consumes the `BitDestuffer` and returns the raw `BitReader`
+    pub fn into_inner(self) -> BitReader<R> {
+        self.reader
+    }
+
+    /// Reads a single destuffed bit.
+    pub fn read_bit(&mut self) -> Result<bool> {
+        if let Some(expected) = self.run.stuff_bit() {
+            let bit_offset = self.reader.bit_cursor();
+            let bit = self.reader.read_bit()?;
+            if bit != expected {
+                return Err(Error::StuffingViolation(bit_offset));
+            }
+            self.run.push(bit);
+        }
+        let bit = self.reader.read_bit()?;
+        self.run.push(bit);
+        Ok(bit)
+    }
+
+    /// Reads a specified number of destuffed bits (up to 64).
+    pub fn read_bits(&mut self, num_bits: u8) -> Result<u64> {
+        if num_bits > 64 {
+            return Err(Error::NotSupported);
+        }
+        let mut value = 0u64;
+        for _ in 0..num_bits {
+            value = (value << 1) | self.read_bit()? as u64;
+        }
+        Ok(value)
+    }
+}
+
+/// Implement the `Read` trait for `BitDestuffer`, a trailing partial byte is not returned.
+impl<R: Read + BinSeek> Read for BitDestuffer<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        for (len, byte) in buffer.iter_mut().enumerate() {
+            match self.read_bits(8) {
+                Ok(value) => *byte = value as u8,
+                Err(Error::File(err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(len)
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(buffer.len())
+    }
+}
+
+/// BitStuffer inserts stuff bits while writing through a `BitWriter`.
+pub struct BitStuffer<W: Write> {
+    writer: BitWriter<W>,
+    run: Run,
+}
+
+impl<W: Write> BitStuffer<W> {
+    pub fn new(writer: W, rule: StuffingRule) -> Self {
+        Self {
+            writer: BitWriter::new(writer),
+            run: Run::new(rule),
+        }
+    }
+
+    /// Returns the number of raw bits written so far, stuff bits included
+    pub fn raw_bit_offset(&self) -> u64 {
+        self.writer.bit_count()
+    }
+
+    /// Writes a single bit, followed by a stuff bit if the run requires one.
+    pub fn write_bit(&mut self, bit: bool) -> Result<()> {
+        self.writer.write_bit(bit)?;
+        self.run.push(bit);
+        if let Some(stuff) = self.run.stuff_bit() {
+            self.writer.write_bit(stuff)?;
+            self.run.push(stuff);
+        }
+        Ok(())
+    }
+
+    /// Writes the lowest `num_bits` (up to 64) bits of `value`, most significant bit first.
+    pub fn write_bits(&mut self, value: u64, num_bits: u8) -> Result<()> {
+        if num_bits > 64 {
+            return Err(Error::NotSupported);
+        }
+        for idx in (0..num_bits).rev() {
+            self.write_bit((value >> idx) & 1 != 0)?;
+        }
+        Ok(())
+    }
+
+    /// Writes bits without stuffing (e.g. an HDLC flag) and starts a new run.
+    pub fn write_raw_bits(&mut self, value: u64, num_bits: u8) -> Result<()> {
+        self.writer.write_bits(value, num_bits)?;
+        self.run = Run::new(self.run.rule);
+        Ok(())
+    }
+
+    /// Pads the last byte with zero bits, flushes and returns the underlying writer.
+    pub fn finish(self) -> Result<W> {
+        self.writer.finish()
+    }
+}
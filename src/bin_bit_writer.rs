@@ -0,0 +1,144 @@
+use std::io::Write;
+
+use crate::bin_bitset::BitSet;
+use crate::bin_error::{Error, Result};
+use crate::bin_packed::{self as packed, BitOrder};
+
+/// A writer for bit-level data, packing bits most significant bit first into bytes.
+///
+/// A partially filled last byte is only written by `byte_align` or `finish`.
+#[derive(Debug)]
+pub struct BitWriter<W: Write> {
+    writer: W,
+    // Bits not yet written, right aligned. Always less than 8 between calls.
+    acc: u64,
+    acc_bits: u32,
+    bit_count: u64,
+}
+
+impl<W: Write> BitWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            acc: 0,
+            acc_bits: 0,
+            bit_count: 0,
+        }
+    }
+
+    /// Returns the number of bits written so far
+    pub fn bit_count(&self) -> u64 {
+        self.bit_count
+    }
+
+    /// Returns true if the next bit starts a new byte.
+    pub fn is_byte_aligned(&self) -> bool {
+        self.acc_bits == 0
+    }
+
+    /// Returns a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Writes a single bit.
+    pub fn write_bit(&mut self, bit: bool) -> Result<()> {
+        self.write_bits(bit as u64, 1)
+    }
+
+    /// Writes the lowest `num_bits` (up to 64) bits of `value`.
+    pub fn write_bits(&mut self, value: u64, num_bits: u8) -> Result<()> {
+        if num_bits > 64 {
+            return Err(Error::NotSupported);
+        }
+        if num_bits > 56 {
+            self.write_bits(value >> 32, num_bits - 32)?;
+            return self.write_bits(value & 0xFFFF_FFFF, 32);
+        }
+        if num_bits == 0 {
+            return Ok(());
+        }
+        let num_bits = num_bits as u32;
+        self.acc = (self.acc << num_bits) | (value & ((1u64 << num_bits) - 1));
+        self.acc_bits += num_bits;
+        self.bit_count += num_bits as u64;
+
+        let mut bytes = [0u8; 8];
+        let mut len = 0;
+        while self.acc_bits >= 8 {
+            self.acc_bits -= 8;
+            bytes[len] = (self.acc >> self.acc_bits) as u8;
+            len += 1;
+        }
+        self.acc &= (1u64 << self.acc_bits) - 1;
+        if len > 0 {
+            self.writer.write_all(&bytes[..len])?;
+        }
+        Ok(())
+    }
+
+    /// Writes all bits of `bits`, highest bit first, the counterpart of `BitReader::read_bitset`.
+    pub fn write_bitset(&mut self, bits: &BitSet) -> Result<()> {
+        let mut remaining = bits.len();
+        for &word in bits.words().iter().rev() {
+            let num_bits = remaining - (remaining - 1) / 64 * 64;
+            self.write_bits(word, num_bits as u8)?;
+            remaining -= num_bits;
+        }
+        Ok(())
+    }
+
+    /// Writes `samples` packed back to back with `bits_per_sample` (1 to 16) bits each, only the
+    /// lowest `bits_per_sample` bits of every sample are used. The counterpart of
+    /// `BitReader::read_packed_into`, with the same alignment and group requirements.
+    /// `LsbFirst` pads a trailing partial byte with zero bits.
+    pub fn write_packed(
+        &mut self,
+        samples: &[u16],
+        bits_per_sample: u8,
+        order: BitOrder,
+    ) -> Result<()> {
+        let (group_samples, _) = order.group(bits_per_sample)?;
+        if order == BitOrder::Mipi && !samples.len().is_multiple_of(group_samples) {
+            return Err(Error::NotSupported);
+        }
+        if !self.is_byte_aligned() {
+            if order != BitOrder::MsbFirst {
+                return Err(Error::NotSupported);
+            }
+            for &sample in samples {
+                self.write_bits(sample as u64, bits_per_sample)?;
+            }
+            return Ok(());
+        }
+
+        // `MsbFirst` writes a trailing partial group bit by bit, so the writer keeps its position.
+        let whole = match order {
+            BitOrder::MsbFirst => samples.len() / group_samples * group_samples,
+            _ => samples.len(),
+        };
+        let mut bytes = Vec::new();
+        packed::pack(&samples[..whole], &mut bytes, bits_per_sample, order);
+        self.writer.write_all(&bytes)?;
+        self.bit_count += bytes.len() as u64 * 8;
+        for &sample in &samples[whole..] {
+            self.write_bits(sample as u64, bits_per_sample)?;
+        }
+        Ok(())
+    }
+
+    /// Pads the current byte with zero bits and writes it.
+    pub fn byte_align(&mut self) -> Result<()> {
+        if self.acc_bits > 0 {
+            self.write_bits(0, (8 - self.acc_bits) as u8)?;
+        }
+        Ok(())
+    }
+
+    /// Pads and writes the last byte, flushes and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.byte_align()?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
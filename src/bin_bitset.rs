@@ -0,0 +1,273 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+/// The number of 64-bit words a `BitSet` keeps without allocating.
+const INLINE_WORDS: usize = 2;
+
+#[derive(Clone)]
+enum Words {
+    Inline([u64; INLINE_WORDS]),
+    Heap(Box<[u64]>),
+}
+
+/// A fixed length set of bits, such as a bitmap field read with `BitReader::read_bitset`.
+///
+/// Bit `i` has the weight `2^i` in the value the bits would form as an unsigned integer,
+/// so the last bit read from a stream is bit 0. Sets of up to 128 bits are stored inline.
+#[derive(Clone)]
+pub struct BitSet {
+    len: usize,
+    words: Words,
+}
+
+impl BitSet {
+    /// Creates a set of `len` bits, all cleared.
+    pub fn new(len: usize) -> Self {
+        let count = len.div_ceil(64);
+        let words = if count <= INLINE_WORDS {
+            Words::Inline([0; INLINE_WORDS])
+        } else {
+            Words::Heap(vec![0; count].into_boxed_slice())
+        };
+        Self { len, words }
+    }
+
+    /// Creates a set of `len` (up to 64) bits from the lowest bits of `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than 64.
+    pub fn from_u64(value: u64, len: usize) -> Self {
+        assert!(len <= 64, "a u64 holds at most 64 bits");
+        let mut set = Self::new(len);
+        if len > 0 {
+            set.words_mut()[0] = value & (u64::MAX >> (64 - len));
+        }
+        set
+    }
+
+    /// Returns the value of the set as an integer, or `None` if it has more than 64 bits.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.len {
+            0 => Some(0),
+            1..=64 => Some(self.words()[0]),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of bits in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the set has no bits at all.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bits as 64-bit words, bit 0 being the lowest bit of the first word.
+    /// Bits past `len` are always zero.
+    pub fn words(&self) -> &[u64] {
+        let count = self.len.div_ceil(64);
+        match &self.words {
+            Words::Inline(words) => &words[..count],
+            Words::Heap(words) => words,
+        }
+    }
+
+    pub(crate) fn words_mut(&mut self) -> &mut [u64] {
+        let count = self.len.div_ceil(64);
+        match &mut self.words {
+            Words::Inline(words) => &mut words[..count],
+            Words::Heap(words) => words,
+        }
+    }
+
+    /// Returns `true` if bit `bit` is set. Bits past the end of the set are never set.
+    pub fn contains(&self, bit: usize) -> bool {
+        bit < self.len && self.words()[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    /// Sets bit `bit`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit` is not less than `len`.
+    pub fn insert(&mut self, bit: usize) {
+        self.set(bit, true);
+    }
+
+    /// Clears bit `bit`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit` is not less than `len`.
+    pub fn remove(&mut self, bit: usize) {
+        self.set(bit, false);
+    }
+
+    /// Sets bit `bit` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit` is not less than `len`.
+    pub fn set(&mut self, bit: usize, value: bool) {
+        assert!(
+            bit < self.len,
+            "bit {} out of range for {} bits",
+            bit,
+            self.len
+        );
+        let word = &mut self.words_mut()[bit / 64];
+        if value {
+            *word |= 1 << (bit % 64);
+        } else {
+            *word &= !(1 << (bit % 64));
+        }
+    }
+
+    /// Returns the number of set bits.
+    pub fn count_ones(&self) -> usize {
+        self.words().iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns `true` if no bit is set.
+    pub fn none(&self) -> bool {
+        self.words().iter().all(|&w| w == 0)
+    }
+
+    /// Returns an iterator over the positions of the set bits, in ascending order.
+    pub fn iter_ones(&self) -> Ones<'_> {
+        Ones {
+            words: self.words(),
+            base: 0,
+            current: self.words().first().copied().unwrap_or(0),
+        }
+    }
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.words() == other.words()
+    }
+}
+
+impl Eq for BitSet {}
+
+impl fmt::Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BitSet({}) ", self.len)?;
+        f.debug_set().entries(self.iter_ones()).finish()
+    }
+}
+
+/// An iterator over the positions of the set bits of a `BitSet`.
+pub struct Ones<'a> {
+    words: &'a [u64],
+    base: usize,
+    current: u64,
+}
+
+impl Iterator for Ones<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            if self.words.len() <= 1 {
+                return None;
+            }
+            self.words = &self.words[1..];
+            self.base += 64;
+            self.current = self.words[0];
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(self.base + bit)
+    }
+}
+
+/// A named flag of a bitmap, mapped to its bit position.
+pub trait Flag: Copy + Sized {
+    /// Returns the flag at bit position `bit`, or `None` if that bit has no name.
+    fn from_bit(bit: usize) -> Option<Self>;
+
+    /// Returns the bit position of the flag.
+    fn bit(self) -> usize;
+}
+
+/// A `BitSet` viewed as a set of typed flags.
+///
+/// Set bits which do not map to a flag are kept and reported by `unknown_bits`, so a
+/// bitmap decoded and written back is not changed.
+#[derive(Clone, PartialEq, Eq)]
+pub struct FlagSet<F: Flag> {
+    bits: BitSet,
+    marker: PhantomData<F>,
+}
+
+impl<F: Flag> FlagSet<F> {
+    pub fn new(bits: BitSet) -> Self {
+        Self {
+            bits,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns `true` if `flag` is set.
+    pub fn contains(&self, flag: F) -> bool {
+        self.bits.contains(flag.bit())
+    }
+
+    /// Sets `flag`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit of `flag` is outside of the bitmap.
+    pub fn insert(&mut self, flag: F) {
+        self.bits.insert(flag.bit());
+    }
+
+    /// Clears `flag`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit of `flag` is outside of the bitmap.
+    pub fn remove(&mut self, flag: F) {
+        self.bits.remove(flag.bit());
+    }
+
+    /// Returns an iterator over the set flags, in ascending bit order.
+    pub fn iter(&self) -> impl Iterator<Item = F> + '_ {
+        self.bits.iter_ones().filter_map(F::from_bit)
+    }
+
+    /// Returns an iterator over the positions of set bits which have no flag.
+    pub fn unknown_bits(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits
+            .iter_ones()
+            .filter(|&bit| F::from_bit(bit).is_none())
+    }
+
+    /// Returns `true` if any set bit has no flag.
+    pub fn has_unknown(&self) -> bool {
+        self.unknown_bits().next().is_some()
+    }
+
+    /// Returns all bits, the unknown ones included.
+    pub fn bits(&self) -> &BitSet {
+        &self.bits
+    }
+
+    pub fn into_bits(self) -> BitSet {
+        self.bits
+    }
+}
+
+impl<F: Flag + fmt::Debug> fmt::Debug for FlagSet<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlagSet")
+            .field("flags", &self.iter().collect::<Vec<_>>())
+            .field("unknown", &self.unknown_bits().collect::<Vec<_>>())
+            .finish()
+    }
+}
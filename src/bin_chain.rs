@@ -0,0 +1,135 @@
+use std::io::{self, Read};
+
+use crate::{bin_error::Result, BinSeek};
+
+/// BinChain presents an ordered list of sources, such as the `.000`, `.001`, ... segments of a
+/// split capture, as one continuous stream with global positions.
+///
+/// The length of every source is taken when it is added. Reads stop at the end of a segment
+/// and continue in the next one, so `read_exact` and parsers reading through `ReadBytes`
+/// cross segment boundaries transparently.
+#[derive(Debug)]
+pub struct BinChain<R: Read + BinSeek> {
+    sources: Vec<R>,
+    // The global position where each source starts, followed by the total length.
+    offsets: Vec<usize>,
+    // The source the position is in, `sources.len()` at the end.
+    current: usize,
+    position: usize,
+}
+
+impl<R: Read + BinSeek> BinChain<R> {
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            offsets: vec![0],
+            current: 0,
+            position: 0,
+        }
+    }
+
+    /// Creates a chain of `sources`, in order
+    pub fn from_sources<I: IntoIterator<Item = R>>(sources: I) -> Result<Self> {
+        let mut chain = Self::new();
+        for source in sources {
+            chain.push(source)?;
+        }
+        Ok(chain)
+    }
+
+    /// Appends `source` to the end of the chain, reading it from its start
+    pub fn push(&mut self, source: R) -> Result<()> {
+        let len = source.len()?;
+        let total = self.total_len();
+        self.offsets.push(total + len);
+        self.sources.push(source);
+        // The position may have been at the old end
+        let position = self.position;
+        self.seek(position)?;
+        Ok(())
+    }
+
+    /// Returns the number of sources in the chain
+    pub fn source_count(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Returns the index of the source holding the global position `pos` and the offset of
+    /// `pos` inside that source, or `None` past the end of the chain
+    pub fn locate(&self, pos: usize) -> Option<(usize, usize)> {
+        if pos >= self.total_len() {
+            return None;
+        }
+        // The last source starting at or before `pos`, skipping empty ones
+        let index = self.offsets.partition_point(|&offset| offset <= pos) - 1;
+        Some((index, pos - self.offsets[index]))
+    }
+
+    /// Returns a reference to the source at `index`
+    pub fn get_ref(&self, index: usize) -> Option<&R> {
+        self.sources.get(index)
+    }
+
+    /// Consumes the `BinChain` and returns the sources
+    pub fn into_inner(self) -> Vec<R> {
+        self.sources
+    }
+
+    fn total_len(&self) -> usize {
+        *self.offsets.last().unwrap_or(&0)
+    }
+}
+
+impl<R: Read + BinSeek> Default for BinChain<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Read + BinSeek> BinSeek for BinChain<R> {
+    /// Seeks to a global position, positions past the end are allowed and read nothing
+    fn seek(&mut self, to: usize) -> Result<usize> {
+        match self.locate(to) {
+            Some((index, offset)) => {
+                self.sources[index].seek(offset)?;
+                self.current = index;
+            }
+            None => self.current = self.sources.len(),
+        }
+        self.position = to;
+        Ok(to)
+    }
+
+    fn pos(&mut self) -> Result<usize> {
+        Ok(self.position)
+    }
+
+    fn len(&self) -> Result<usize> {
+        Ok(self.total_len())
+    }
+}
+
+impl<R: Read + BinSeek> Read for BinChain<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        while self.current < self.sources.len() && !buffer.is_empty() {
+            let remaining = self.offsets[self.current + 1] - self.position;
+            let limit = buffer.len().min(remaining);
+            let len = self.sources[self.current].read(&mut buffer[..limit])?;
+            if len > 0 {
+                self.position += len;
+                if len == remaining {
+                    self.seek(self.position)?;
+                }
+                return Ok(len);
+            }
+            if remaining > 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "source ended before its length",
+                ));
+            }
+            self.seek(self.position)?;
+        }
+        Ok(0)
+    }
+}
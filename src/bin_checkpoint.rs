@@ -0,0 +1,179 @@
+use std::{
+    io::{Read, Write},
+    ops::{Deref, DerefMut},
+};
+
+use crate::{bin_error::Result, BinSeek, BitReader};
+
+/// Checkpoint is a guard which remembers the position of a `BinSeek` source and rewinds
+/// to it when dropped, unless `commit` was called. Created by `BinSeek::checkpoint`.
+///
+/// The guard dereferences to the source, so parsing continues through it and checkpoints
+/// can be nested by calling `checkpoint` on the guard.
+#[derive(Debug)]
+pub struct Checkpoint<'a, S: BinSeek + ?Sized> {
+    source: &'a mut S,
+    position: usize,
+    committed: bool,
+}
+
+impl<'a, S: BinSeek + ?Sized> Checkpoint<'a, S> {
+    pub(crate) fn new(source: &'a mut S) -> Result<Self> {
+        let position = source.pos()?;
+        Ok(Self {
+            source,
+            position,
+            committed: false,
+        })
+    }
+
+    /// Returns the position the source is rewound to
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Keeps everything read since the checkpoint was taken
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+
+    /// Rewinds to the checkpoint now, reporting a failed seek instead of ignoring it as `drop` does
+    pub fn rollback(mut self) -> Result<()> {
+        self.committed = true;
+        self.source.seek(self.position)?;
+        Ok(())
+    }
+}
+
+impl<S: BinSeek + ?Sized> Deref for Checkpoint<'_, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.source
+    }
+}
+
+impl<S: BinSeek + ?Sized> DerefMut for Checkpoint<'_, S> {
+    fn deref_mut(&mut self) -> &mut S {
+        self.source
+    }
+}
+
+impl<S: BinSeek + ?Sized> Drop for Checkpoint<'_, S> {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = self.source.seek(self.position);
+        }
+    }
+}
+
+impl<S: BinSeek + Read + ?Sized> Read for Checkpoint<'_, S> {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        self.source.read(buffer)
+    }
+}
+
+impl<S: BinSeek + Write + ?Sized> Write for Checkpoint<'_, S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.source.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.source.flush()
+    }
+}
+
+impl<S: BinSeek + ?Sized> BinSeek for Checkpoint<'_, S> {
+    fn seek(&mut self, to: usize) -> Result<usize> {
+        self.source.seek(to)
+    }
+
+    fn pos(&mut self) -> Result<usize> {
+        self.source.pos()
+    }
+
+    fn len(&self) -> Result<usize> {
+        self.source.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.source.is_empty()
+    }
+
+    fn is_eof(&mut self) -> bool {
+        self.source.is_eof()
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.source.is_seekable()
+    }
+}
+
+/// BitCheckpoint is the `BitReader` counterpart of `Checkpoint`, it remembers the bit position
+/// and rewinds to it when dropped, unless `commit` was called. Created by `BitReader::checkpoint`.
+///
+/// Rewinding to a position the reader still holds in its cache or internal buffer works on
+/// any source, further back it needs a seekable source.
+pub struct BitCheckpoint<'a, R: Read + BinSeek> {
+    reader: &'a mut BitReader<R>,
+    bit_pos: u64,
+    committed: bool,
+}
+
+impl<'a, R: Read + BinSeek> BitCheckpoint<'a, R> {
+    pub(crate) fn new(reader: &'a mut BitReader<R>) -> Self {
+        let bit_pos = reader.bit_cursor();
+        Self {
+            reader,
+            bit_pos,
+            committed: false,
+        }
+    }
+
+    /// Returns the bit position the reader is rewound to
+    pub fn bit_position(&self) -> u64 {
+        self.bit_pos
+    }
+
+    /// Keeps everything read since the checkpoint was taken
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+
+    /// Rewinds to the checkpoint now, reporting a failed seek instead of ignoring it as `drop` does
+    pub fn rollback(mut self) -> Result<()> {
+        self.committed = true;
+        self.reader.seek_bits(self.bit_pos as usize)
+    }
+}
+
+impl<R: Read + BinSeek> Deref for BitCheckpoint<'_, R> {
+    type Target = BitReader<R>;
+
+    fn deref(&self) -> &BitReader<R> {
+        self.reader
+    }
+}
+
+impl<R: Read + BinSeek> DerefMut for BitCheckpoint<'_, R> {
+    fn deref_mut(&mut self) -> &mut BitReader<R> {
+        self.reader
+    }
+}
+
+impl<R: Read + BinSeek> Drop for BitCheckpoint<'_, R> {
+    /// Rewinds unless committed. A failed rewind, e.g. on a source which is not seekable read
+    /// past the internal buffer, is ignored and leaves the reader where it is, use `rollback`
+    /// to get the error.
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = self.reader.seek_bits(self.bit_pos as usize);
+        }
+    }
+}
+
+impl<R: Read + BinSeek> Read for BitCheckpoint<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buffer)
+    }
+}
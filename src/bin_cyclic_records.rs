@@ -0,0 +1,56 @@
+use std::vec;
+
+use crate::{BinMemoryBuffer, BinRingMemoryBuffer};
+
+/// CyclicRecords iterates the records of a cyclic area from the oldest to the newest, or
+/// backwards with `rev`, each as a `BinMemoryBuffer`. Records crossing the end of the area
+/// are joined. Created by `BinRingMemoryBuffer::fixed_records` or
+/// `BinRingMemoryBuffer::length_prefixed_records`.
+#[derive(Debug)]
+pub struct CyclicRecords<'a> {
+    ring: &'a BinRingMemoryBuffer,
+    oldest: usize,
+    // The start of each record relative to `oldest`, with its length.
+    records: vec::IntoIter<(usize, usize)>,
+}
+
+impl<'a> CyclicRecords<'a> {
+    pub(crate) fn new(
+        ring: &'a BinRingMemoryBuffer,
+        oldest: usize,
+        records: Vec<(usize, usize)>,
+    ) -> Self {
+        Self {
+            ring,
+            oldest,
+            records: records.into_iter(),
+        }
+    }
+
+    fn record(&self, (start, len): (usize, usize)) -> BinMemoryBuffer {
+        let index = (self.oldest + start) % self.ring.capacity();
+        BinMemoryBuffer::from(self.ring.copy_range(index, len))
+    }
+}
+
+impl Iterator for CyclicRecords<'_> {
+    type Item = BinMemoryBuffer;
+
+    fn next(&mut self) -> Option<BinMemoryBuffer> {
+        let record = self.records.next()?;
+        Some(self.record(record))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.records.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for CyclicRecords<'_> {
+    fn next_back(&mut self) -> Option<BinMemoryBuffer> {
+        let record = self.records.next_back()?;
+        Some(self.record(record))
+    }
+}
+
+impl ExactSizeIterator for CyclicRecords<'_> {}
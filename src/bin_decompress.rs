@@ -0,0 +1,252 @@
+use std::io::{self, Read};
+
+#[cfg(feature = "xz")]
+use lzma_rust2::XzReader;
+#[cfg(feature = "zstd")]
+use ruzstd::decoding::{FrameDecoder, StreamingDecoder};
+
+use crate::{
+    bin_error::{Error, Result},
+    BinMemoryBuffer, BinSeek, BinStream, InflateReader,
+};
+
+/// The longest magic number which is checked.
+const MAGIC_LEN: usize = 6;
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+const XZ_MAGIC: &[u8] = &[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00];
+
+/// A compression format, recognised by the magic number at the start of the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// The data is not compressed, or compressed with an unknown format.
+    None,
+    /// gzip, decompressed with the built-in `InflateReader`.
+    Gzip,
+    /// Zstandard, needs the `zstd` feature.
+    Zstd,
+    /// xz, needs the `xz` feature.
+    Xz,
+}
+
+impl Compression {
+    /// Returns the format of data starting with `magic`.
+    pub fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if magic.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if magic.starts_with(XZ_MAGIC) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// A decompressor over the compressed source, which can be turned back into the source.
+enum Decoder<R: Read> {
+    Gzip(Box<InflateReader<R>>),
+    #[cfg(feature = "zstd")]
+    Zstd(Box<StreamingDecoder<R, FrameDecoder>>),
+    #[cfg(feature = "xz")]
+    Xz(Box<XzReader<R>>),
+}
+
+impl<R: Read> Decoder<R> {
+    fn new(source: R, compression: Compression) -> Result<Self> {
+        match compression {
+            Compression::Gzip => Ok(Decoder::Gzip(Box::new(InflateReader::gzip(source)))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => StreamingDecoder::new(source)
+                .map(|decoder| Decoder::Zstd(Box::new(decoder)))
+                .map_err(|err| Error::File(io::Error::new(io::ErrorKind::InvalidData, err))),
+            #[cfg(feature = "xz")]
+            Compression::Xz => Ok(Decoder::Xz(Box::new(XzReader::new(source, true)))),
+            _ => Err(Error::NotSupported),
+        }
+    }
+
+    fn into_inner(self) -> R {
+        match self {
+            Decoder::Gzip(decoder) => decoder.into_inner(),
+            #[cfg(feature = "zstd")]
+            Decoder::Zstd(decoder) => decoder.into_inner(),
+            #[cfg(feature = "xz")]
+            Decoder::Xz(decoder) => decoder.into_inner(),
+        }
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Decoder::Gzip(decoder) => decoder.read(buffer),
+            #[cfg(feature = "zstd")]
+            Decoder::Zstd(decoder) => decoder.read(buffer),
+            #[cfg(feature = "xz")]
+            Decoder::Xz(decoder) => decoder.read(buffer),
+        }
+    }
+}
+
+enum Inner<R: Read + BinSeek> {
+    Plain(R),
+    Memory(BinMemoryBuffer),
+    // `None` only after a failed restart.
+    Restart(Option<BinStream<Decoder<R>>>),
+}
+
+/// BinDecompressReader reads gzip, zstd or xz compressed data as if it was not compressed,
+/// picking the format by its magic number. Data without a known magic number is read as is.
+/// Created by `BinReader::open_decompressed` or `BinDecompressReader::new`.
+///
+/// Data up to a size limit is decompressed into memory and supports random access like a
+/// `BinMemoryBuffer`. Larger data is decompressed while it is read, seeking forward skips
+/// data and seeking backwards restarts decompression from the start. In that mode `len` is
+/// only known once the end has been read and returns `Error::NotSupported` before.
+pub struct BinDecompressReader<R: Read + BinSeek> {
+    inner: Inner<R>,
+    compression: Compression,
+    // The position of the compressed data in the source.
+    start: usize,
+    len: Option<usize>,
+}
+
+impl<R: Read + BinSeek> BinDecompressReader<R> {
+    /// Detects the compression of the data at the current position of `source`.
+    /// Data which decompresses to at most `memory_limit` bytes is kept in memory,
+    /// a limit of 0 always decompresses while reading.
+    pub fn new(mut source: R, memory_limit: usize) -> Result<Self> {
+        let start = source.pos()?;
+        let mut magic = Vec::with_capacity(MAGIC_LEN);
+        (&mut source)
+            .take(MAGIC_LEN as u64)
+            .read_to_end(&mut magic)?;
+        source.seek(start)?;
+
+        let compression = Compression::detect(&magic);
+        if compression == Compression::None {
+            let len = source.len()?.saturating_sub(start);
+            return Ok(Self {
+                inner: Inner::Plain(source),
+                compression,
+                start,
+                len: Some(len),
+            });
+        }
+
+        let mut reader = Self {
+            inner: Inner::Restart(None),
+            compression,
+            start,
+            len: None,
+        };
+        let mut decoder = Decoder::new(source, compression)?;
+        if memory_limit > 0 {
+            let mut data = Vec::new();
+            (&mut decoder)
+                .take(memory_limit as u64 + 1)
+                .read_to_end(&mut data)?;
+            if data.len() <= memory_limit {
+                reader.len = Some(data.len());
+                reader.inner = Inner::Memory(BinMemoryBuffer::from(data));
+                return Ok(reader);
+            }
+            decoder = reader.restart_decoder(decoder)?;
+        }
+        reader.inner = Inner::Restart(Some(BinStream::new(decoder)));
+        Ok(reader)
+    }
+
+    /// Returns the compression format of the source
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Returns true if all decompressed data is held in memory
+    pub fn is_in_memory(&self) -> bool {
+        matches!(self.inner, Inner::Memory(_))
+    }
+
+    /// Rewinds the source of `decoder` and starts decompressing again.
+    fn restart_decoder(&self, decoder: Decoder<R>) -> Result<Decoder<R>> {
+        let mut source = decoder.into_inner();
+        source.seek(self.start)?;
+        Decoder::new(source, self.compression)
+    }
+
+    fn stream(&mut self) -> Result<&mut BinStream<Decoder<R>>> {
+        match &mut self.inner {
+            Inner::Restart(Some(stream)) => Ok(stream),
+            _ => Err(Error::NotSupported),
+        }
+    }
+}
+
+impl<R: Read + BinSeek> Read for BinDecompressReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Inner::Plain(source) => source.read(buffer),
+            Inner::Memory(memory) => memory.read(buffer),
+            Inner::Restart(_) => {
+                let stream = self.stream()?;
+                let len = stream.read(buffer)?;
+                if len == 0 && !buffer.is_empty() {
+                    self.len = Some(stream.pos()?);
+                }
+                Ok(len)
+            }
+        }
+    }
+}
+
+impl<R: Read + BinSeek> BinSeek for BinDecompressReader<R> {
+    fn seek(&mut self, to: usize) -> Result<usize> {
+        match &mut self.inner {
+            Inner::Plain(source) => Ok(source.seek(self.start + to)? - self.start),
+            Inner::Memory(memory) => memory.seek(to),
+            Inner::Restart(stream) => {
+                let mut current = stream.take().ok_or(Error::NotSupported)?;
+                if to < current.pos()? {
+                    current = BinStream::new(self.restart_decoder(current.into_inner())?);
+                }
+                let result = current.seek(to);
+                self.inner = Inner::Restart(Some(current));
+                result
+            }
+        }
+    }
+
+    fn pos(&mut self) -> Result<usize> {
+        match &mut self.inner {
+            Inner::Plain(source) => Ok(source.pos()? - self.start),
+            Inner::Memory(memory) => memory.pos(),
+            Inner::Restart(_) => self.stream()?.pos(),
+        }
+    }
+
+    fn len(&self) -> Result<usize> {
+        self.len.ok_or(Error::NotSupported)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == Some(0)
+    }
+
+    fn is_eof(&mut self) -> bool {
+        match &mut self.inner {
+            Inner::Plain(source) => source.is_eof(),
+            Inner::Memory(memory) => memory.is_eof(),
+            Inner::Restart(Some(stream)) => {
+                let eof = stream.is_eof();
+                if eof {
+                    self.len = stream.pos().ok();
+                }
+                eof
+            }
+            Inner::Restart(None) => true,
+        }
+    }
+}
@@ -7,6 +7,12 @@ pub enum Error {
     InvalidInputArray,
     File(io::Error),
     NotSupported,
+    InvalidHuffmanTable,
+    InvalidHuffmanCode,
+    InvalidDeflateData(&'static str),
+    ChecksumMismatch,
+    StuffingViolation(u64),
+    InvalidRecordArea(&'static str),
 }
 
 impl fmt::Display for Error {
@@ -16,6 +22,14 @@ impl fmt::Display for Error {
             InvalidInputArray => f.write_str("conversion from array to slice fails"),
             File(err) => f.write_str(err.to_string().as_str()),
             NotSupported => f.write_str("Operation are not supported."),
+            InvalidHuffmanTable => f.write_str("over-subscribed or malformed huffman code lengths"),
+            InvalidHuffmanCode => f.write_str("bit sequence is not a valid huffman code"),
+            InvalidDeflateData(msg) => write!(f, "invalid deflate data: {}", msg),
+            ChecksumMismatch => f.write_str("checksum of the decompressed data does not match"),
+            StuffingViolation(bit_offset) => {
+                write!(f, "bit stuffing violation at raw bit offset {}", bit_offset)
+            }
+            InvalidRecordArea(msg) => write!(f, "invalid cyclic record area: {}", msg),
         }
     }
 }
@@ -28,6 +42,15 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<Error> for std::io::Error {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::File(err) => err,
+            err => std::io::Error::other(err),
+        }
+    }
+}
+
 impl From<TryFromSliceError> for Error {
     fn from(_: TryFromSliceError) -> Self {
         Error::InvalidInputArray
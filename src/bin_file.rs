@@ -1,97 +1,329 @@
+#[cfg(unix)]
+use std::os::unix::fs::FileExt;
 use std::{
-    fs::{File, Metadata},
+    fs::{File, Metadata, TryLockError},
     io::{Read, Seek, SeekFrom, Write},
 };
 
+#[cfg(unix)]
+use crate::SharedBinFile;
 use crate::{
     bin_error::{Error, Result},
-    BinSeek,
+    bin_file_buffer::FileBuffer,
+    BinOpenOptions, BinSeek,
 };
 
 /// Struct to represent a binary file with metadata
+///
+/// The length used by `len`, `is_eof` and the read checks is kept up to date by writes through
+/// the `BinFile`. Changes made to the file by others are picked up by `refresh_metadata`.
+///
+/// A buffered `BinFile` (see `buffered`) serves small reads and writes from one in-memory
+/// window of the file. Seeking only moves the position, so seeks inside the window keep it,
+/// pending writes are written by `flush`, before the window moves and on drop.
 #[derive(Debug)]
 pub struct BinFile {
     file: File,
     metadata: Metadata,
+    len: u64,
+    append: bool,
+    buffer: Option<FileBuffer>,
 }
 
 impl BinFile {
     // Creates a new instance of `BinFile`, initializing the file and retrieving its metadata
     pub(crate) fn new(file: File) -> Result<Self> {
+        let metadata = file.metadata()?;
         Ok(BinFile {
-            metadata: file.metadata()?,
+            len: metadata.len(),
+            metadata,
             file,
+            append: false,
+            buffer: None,
         })
     }
 
-    /// Returns a reference to the file's metadata
+    // Creates a `BinFile` opened in append mode, where every write goes to the end
+    pub(crate) fn new_append(file: File) -> Result<Self> {
+        let mut bin_file = Self::new(file)?;
+        bin_file.append = true;
+        Ok(bin_file)
+    }
+
+    /// Switches to buffered access with a buffer of `capacity` bytes, 0 switches buffering off
+    pub fn buffered(mut self, capacity: usize) -> Result<Self> {
+        if let Some(mut buffer) = self.buffer.take() {
+            buffer.flush(&mut self.file)?;
+            self.file.seek(SeekFrom::Start(buffer.pos()))?;
+        }
+        if capacity > 0 {
+            let pos = self.file.stream_position()?;
+            self.buffer = Some(FileBuffer::new(capacity, pos));
+        }
+        Ok(self)
+    }
+
+    /// Returns true if reads and writes go through an in-memory buffer
+    pub fn is_buffered(&self) -> bool {
+        self.buffer.is_some()
+    }
+
+    // Returns the current position, without a syscall when buffered
+    fn position(&mut self) -> std::io::Result<u64> {
+        match &self.buffer {
+            Some(buffer) => Ok(buffer.pos()),
+            None => self.file.stream_position(),
+        }
+    }
+
+    // Writes pending buffered data and forgets the buffered window
+    fn invalidate_buffer(&mut self) -> std::io::Result<()> {
+        match &mut self.buffer {
+            Some(buffer) => buffer.invalidate(&mut self.file),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns a builder for opening a file with specific access, such as read and write
+    pub fn options() -> BinOpenOptions {
+        BinOpenOptions::new()
+    }
+
+    /// Returns a reference to the file's metadata, as of opening or the last `refresh_metadata`
     pub fn metadata(&self) -> &Metadata {
         &self.metadata
     }
 
+    /// Reloads the metadata and the length from the file system
+    pub fn refresh_metadata(&mut self) -> Result<&Metadata> {
+        self.invalidate_buffer()?;
+        self.metadata = self.file.metadata()?;
+        self.len = self.metadata.len();
+        Ok(&self.metadata)
+    }
+
+    /// Truncates or extends the file to `size` bytes, the position is not changed
+    pub fn set_len(&mut self, size: u64) -> Result<()> {
+        self.invalidate_buffer()?;
+        self.file.set_len(size)?;
+        self.len = size;
+        Ok(())
+    }
+
+    /// Truncates the file at the current position
+    pub fn truncate(&mut self) -> Result<()> {
+        let pos = self.position()?;
+        self.set_len(pos)
+    }
+
+    /// Flushes all data and metadata of the file to the disk
+    pub fn sync_all(&mut self) -> Result<()> {
+        self.flush()?;
+        self.file.sync_all()?;
+        Ok(())
+    }
+
+    /// Flushes the data of the file to the disk, without metadata which is not needed to read it
+    pub fn sync_data(&mut self) -> Result<()> {
+        self.flush()?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
     /// Reads `buffer.len()` bytes from the file into `buffer`, checking for EOF
     pub fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<()> {
-        if self.file.stream_position()? + buffer.len() as u64 > self.metadata.len() {
+        if self.position()? + buffer.len() as u64 > self.len {
             return Err(Error::File(std::io::Error::new(
                 std::io::ErrorKind::UnexpectedEof,
                 "end of file",
             )));
         }
-        self.file.read_exact(buffer)?;
+        self.read_exact(buffer)?;
         Ok(())
     }
 
     /// Reads exactly `N` bytes from the file and returns them as an array
     pub fn read_n_bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
-        if self.file.stream_position()? + N as u64 > self.metadata.len() {
+        if self.position()? + N as u64 > self.len {
             return Err(Error::File(std::io::Error::new(
                 std::io::ErrorKind::UnexpectedEof,
                 "end of file",
             )));
         }
         let mut buffer: [u8; N] = [0; N];
-        self.file.read_exact(&mut buffer)?;
+        self.read_exact(&mut buffer)?;
         Ok(buffer)
     }
 
     /// Skips `N` bytes from the current file position
     pub fn skip_n_bytes<const N: usize>(&mut self) -> Result<()> {
-        if self.file.stream_position()? + N as u64 > self.metadata.len() {
+        if self.position()? + N as u64 > self.len {
             return Err(Error::File(std::io::Error::new(
                 std::io::ErrorKind::UnexpectedEof,
                 "end of file",
             )));
         }
-        self.file.seek(SeekFrom::Current(N as i64))?;
+        match &mut self.buffer {
+            Some(buffer) => buffer.seek(buffer.pos() + N as u64),
+            None => {
+                self.file.seek(SeekFrom::Current(N as i64))?;
+            }
+        }
         Ok(())
     }
 
     /// Checks if the current position is at the end of the file (EOF)
     pub fn is_eof(&mut self) -> Result<bool> {
-        Ok(self.file.stream_position()? >= self.metadata.len())
+        Ok(self.position()? >= self.len)
     }
 
     /// Write `buffer.len()` bytes from the `buffer` into file
     pub fn write_bytes(&mut self, buffer: &[u8]) -> Result<()> {
-        self.file.write_all(buffer)?;
+        self.write_all(buffer)?;
+        Ok(())
+    }
+}
+
+/// The kind of advisory lock held on a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileLock {
+    /// Any number of handles can hold a shared lock, while no one holds an exclusive lock
+    Shared,
+    /// Only one handle can hold an exclusive lock
+    Exclusive,
+}
+
+/// Advisory locking (`flock` on Linux). Locks are only honoured by processes which lock as well,
+/// they belong to the opened file and are released by `unlock` or when the `BinFile` is dropped.
+impl BinFile {
+    /// Waits until a shared lock is acquired
+    pub fn lock_shared(&self) -> Result<()> {
+        self.lock(FileLock::Shared)
+    }
+
+    /// Waits until an exclusive lock is acquired
+    pub fn lock_exclusive(&self) -> Result<()> {
+        self.lock(FileLock::Exclusive)
+    }
+
+    /// Waits until a lock of the kind `lock` is acquired
+    pub fn lock(&self, lock: FileLock) -> Result<()> {
+        match lock {
+            FileLock::Shared => self.file.lock_shared()?,
+            FileLock::Exclusive => self.file.lock()?,
+        }
+        Ok(())
+    }
+
+    /// Tries to acquire an exclusive lock, returns false if someone else holds a lock
+    pub fn try_lock(&self) -> Result<bool> {
+        self.try_lock_as(FileLock::Exclusive)
+    }
+
+    /// Tries to acquire a shared lock, returns false if someone else holds an exclusive lock
+    pub fn try_lock_shared(&self) -> Result<bool> {
+        self.try_lock_as(FileLock::Shared)
+    }
+
+    /// Tries to acquire a lock of the kind `lock` without waiting
+    pub fn try_lock_as(&self, lock: FileLock) -> Result<bool> {
+        let result = match lock {
+            FileLock::Shared => self.file.try_lock_shared(),
+            FileLock::Exclusive => self.file.try_lock(),
+        };
+        match result {
+            Ok(()) => Ok(true),
+            Err(TryLockError::WouldBlock) => Ok(false),
+            Err(TryLockError::Error(err)) => Err(Error::File(err)),
+        }
+    }
+
+    /// Releases the lock held by this file
+    pub fn unlock(&self) -> Result<()> {
+        self.file.unlock()?;
+        Ok(())
+    }
+}
+
+/// Positional access, which neither uses nor moves the position of the `BinFile`
+#[cfg(unix)]
+impl BinFile {
+    /// Reads up to `buffer.len()` bytes at `offset`, returns the number of bytes read.
+    /// The file is read directly, writes still pending in the buffer of a buffered `BinFile`
+    /// are not seen.
+    pub fn read_at(&self, buffer: &mut [u8], offset: u64) -> Result<usize> {
+        Ok(self.file.read_at(buffer, offset)?)
+    }
+
+    /// Reads exactly `buffer.len()` bytes at `offset`, see `read_at`
+    pub fn read_exact_at(&self, buffer: &mut [u8], offset: u64) -> Result<()> {
+        if offset
+            .checked_add(buffer.len() as u64)
+            .is_none_or(|end| end > self.len)
+        {
+            return Err(Error::File(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "end of file",
+            )));
+        }
+        self.file.read_exact_at(buffer, offset)?;
+        Ok(())
+    }
+
+    /// Writes all of `buffer` at `offset`. Pending buffered writes are written first and the
+    /// buffered window is dropped, so later reads see the new data.
+    pub fn write_at(&mut self, buffer: &[u8], offset: u64) -> Result<()> {
+        let end = offset.checked_add(buffer.len() as u64).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "offset is past the largest file size",
+            )
+        })?;
+        self.invalidate_buffer()?;
+        self.file.write_all_at(buffer, offset)?;
+        self.len = self.len.max(end);
         Ok(())
     }
+
+    /// Converts into a `SharedBinFile` positioned at the current position.
+    /// Pending buffered writes are written first.
+    pub fn into_shared(mut self) -> Result<SharedBinFile> {
+        self.flush()?;
+        let pos = self.position()?;
+        SharedBinFile::new(self.file.try_clone()?, pos)
+    }
 }
 
 // Implementing the `Read` trait for `BinFile`, allowing it to be read like any other `Read` type
 impl Read for BinFile {
     fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
-        self.file.read(buffer)
+        match &mut self.buffer {
+            Some(file_buffer) => file_buffer.read(&mut self.file, buffer),
+            None => self.file.read(buffer),
+        }
     }
 }
 
 // Implementing the `Write` trait for `BinFile`, allowing it to be write like any other `Write` type
 impl Write for BinFile {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        self.file.write(buf)
+        let written = match &mut self.buffer {
+            Some(buffer) => {
+                if self.append {
+                    buffer.seek(self.len);
+                }
+                buffer.write(&mut self.file, buf)?
+            }
+            None => self.file.write(buf)?,
+        };
+        self.len = self.len.max(self.position()?);
+        Ok(written)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
+        if let Some(buffer) = &mut self.buffer {
+            buffer.flush(&mut self.file)?;
+        }
         self.file.flush()
     }
 }
@@ -99,16 +331,30 @@ impl Write for BinFile {
 impl BinSeek for BinFile {
     /// Seeks to a specific position (in bytes) within the file
     fn seek(&mut self, to: usize) -> Result<usize> {
-        Ok(self.file.seek(SeekFrom::Start(to as u64))? as usize)
+        match &mut self.buffer {
+            Some(buffer) => {
+                buffer.seek(to as u64);
+                Ok(to)
+            }
+            None => Ok(self.file.seek(SeekFrom::Start(to as u64))? as usize),
+        }
     }
 
     /// Returns the current position (in bytes) within the file
     fn pos(&mut self) -> Result<usize> {
-        Ok(self.file.stream_position()? as usize)
+        Ok(self.position()? as usize)
     }
 
     /// Returns the length (size) of the file
     fn len(&self) -> Result<usize> {
-        Ok(self.metadata.len() as usize)
+        Ok(self.len as usize)
+    }
+}
+
+impl Drop for BinFile {
+    fn drop(&mut self) {
+        if let Some(buffer) = &mut self.buffer {
+            let _ = buffer.flush(&mut self.file);
+        }
     }
 }
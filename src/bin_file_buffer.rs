@@ -0,0 +1,124 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+};
+
+/// A window of a file kept in memory, shared by reads and writes of a buffered `BinFile`.
+///
+/// `data` holds the bytes at `start..start + data.len()` as the file will contain them,
+/// bytes in `dirty` are not written yet. The logical position is tracked here, the OS
+/// cursor of the file is always positioned explicitly before it is used.
+#[derive(Debug)]
+pub(crate) struct FileBuffer {
+    data: Vec<u8>,
+    capacity: usize,
+    start: u64,
+    pos: u64,
+    dirty: Option<(usize, usize)>,
+}
+
+impl FileBuffer {
+    pub(crate) fn new(capacity: usize, pos: u64) -> Self {
+        Self {
+            data: Vec::with_capacity(capacity),
+            capacity,
+            start: pos,
+            pos,
+            dirty: None,
+        }
+    }
+
+    pub(crate) fn pos(&self) -> u64 {
+        self.pos
+    }
+
+    /// Moves the logical position, the buffer is kept.
+    pub(crate) fn seek(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    /// Returns the offset of the position into `data`, if it is inside the buffered window
+    /// or (with `at_end`) directly after it.
+    fn offset(&self, at_end: bool) -> Option<usize> {
+        let offset = self.pos.checked_sub(self.start)?;
+        let len = self.data.len() as u64;
+        if offset < len || (at_end && offset == len) {
+            Some(offset as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Writes pending data to the file.
+    pub(crate) fn flush(&mut self, file: &mut File) -> io::Result<()> {
+        if let Some((from, to)) = self.dirty {
+            file.seek(SeekFrom::Start(self.start + from as u64))?;
+            file.write_all(&self.data[from..to])?;
+            self.dirty = None;
+        }
+        Ok(())
+    }
+
+    /// Writes pending data and forgets the buffered window, for when the file changed below it.
+    pub(crate) fn invalidate(&mut self, file: &mut File) -> io::Result<()> {
+        self.flush(file)?;
+        self.data.clear();
+        self.start = self.pos;
+        Ok(())
+    }
+
+    pub(crate) fn read(&mut self, file: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+        if self.offset(false).is_none() {
+            self.flush(file)?;
+            file.seek(SeekFrom::Start(self.pos))?;
+            if buf.len() >= self.capacity {
+                let read = file.read(buf)?;
+                self.pos += read as u64;
+                return Ok(read);
+            }
+            self.data.resize(self.capacity, 0);
+            let read = file.read(&mut self.data)?;
+            self.data.truncate(read);
+            self.start = self.pos;
+            if read == 0 {
+                return Ok(0);
+            }
+        }
+
+        let offset = (self.pos - self.start) as usize;
+        let len = buf.len().min(self.data.len() - offset);
+        buf[..len].copy_from_slice(&self.data[offset..offset + len]);
+        self.pos += len as u64;
+        Ok(len)
+    }
+
+    pub(crate) fn write(&mut self, file: &mut File, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() >= self.capacity {
+            self.invalidate(file)?;
+            file.seek(SeekFrom::Start(self.pos))?;
+            let written = file.write(buf)?;
+            self.pos += written as u64;
+            self.start = self.pos;
+            return Ok(written);
+        }
+
+        let offset = match self.offset(true) {
+            Some(offset) if offset + buf.len() <= self.capacity => offset,
+            _ => {
+                self.invalidate(file)?;
+                0
+            }
+        };
+        let end = offset + buf.len();
+        if end > self.data.len() {
+            self.data.resize(end, 0);
+        }
+        self.data[offset..end].copy_from_slice(buf);
+        self.dirty = Some(match self.dirty {
+            Some((from, to)) => (from.min(offset), to.max(end)),
+            None => (offset, end),
+        });
+        self.pos += buf.len() as u64;
+        Ok(buf.len())
+    }
+}
@@ -0,0 +1,168 @@
+use std::io::Read;
+
+use crate::{
+    bin_error::{Error, Result},
+    BinSeek, BitReader,
+};
+
+/// The longest supported code, enough for JPEG (16) and DEFLATE (15).
+const MAX_CODE_LENGTH: usize = 16;
+
+/// Codes up to this length are decoded with a single table lookup.
+const LOOKUP_BITS: u32 = 9;
+
+/// HuffmanTable decodes canonical Huffman codes, read most significant bit first,
+/// from a `BitReader`.
+///
+/// Codes up to 9 bits are resolved with one lookup on the peeked bits, longer codes
+/// fall back to a canonical search over the code lengths. Incomplete code sets are
+/// accepted (JPEG never assigns the all-ones code), over-subscribed ones are not.
+#[derive(Debug, Clone)]
+pub struct HuffmanTable {
+    // Indexed by the next `lookup_bits` bits: `symbol << 8 | length`, a length of 0
+    // means the code is longer than `lookup_bits` (or does not exist).
+    lookup: Vec<u32>,
+    lookup_bits: u32,
+    max_length: u32,
+    // For each length: the first canonical code, the number of codes and the index
+    // of the first symbol with that length in `symbols`.
+    first_code: [u32; MAX_CODE_LENGTH + 1],
+    count: [u32; MAX_CODE_LENGTH + 1],
+    first_index: [u32; MAX_CODE_LENGTH + 1],
+    // Symbols sorted by code.
+    symbols: Vec<u16>,
+}
+
+impl HuffmanTable {
+    /// Builds the table from the code length of every symbol (`lengths[symbol]`),
+    /// a length of 0 means the symbol is not used. This is how DEFLATE describes its codes.
+    pub fn from_lengths(lengths: &[u8]) -> Result<Self> {
+        if lengths.len() > u16::MAX as usize + 1
+            || lengths.iter().any(|&len| len as usize > MAX_CODE_LENGTH)
+        {
+            return Err(Error::InvalidHuffmanTable);
+        }
+        let mut symbols = Vec::with_capacity(lengths.len());
+        for len in 1..=MAX_CODE_LENGTH as u8 {
+            symbols.extend(
+                (0..lengths.len())
+                    .filter(|&s| lengths[s] == len)
+                    .map(|s| s as u16),
+            );
+        }
+        Self::build(&count_codes(lengths), symbols)
+    }
+
+    /// Builds the table from the number of codes of each length (1 to 16) followed by
+    /// the symbols in code order. This is how JPEG DHT segments describe their codes.
+    pub fn from_counts(counts: &[u8], symbols: &[u16]) -> Result<Self> {
+        if counts.len() > MAX_CODE_LENGTH {
+            return Err(Error::InvalidHuffmanTable);
+        }
+        let mut code_counts = [0u32; MAX_CODE_LENGTH + 1];
+        for (idx, &count) in counts.iter().enumerate() {
+            code_counts[idx + 1] = count as u32;
+        }
+        if code_counts.iter().sum::<u32>() as usize != symbols.len() {
+            return Err(Error::InvalidHuffmanTable);
+        }
+        Self::build(&code_counts, symbols.to_vec())
+    }
+
+    fn build(counts: &[u32; MAX_CODE_LENGTH + 1], symbols: Vec<u16>) -> Result<Self> {
+        let mut first_code = [0u32; MAX_CODE_LENGTH + 1];
+        let mut first_index = [0u32; MAX_CODE_LENGTH + 1];
+        let mut code = 0u32;
+        let mut index = 0u32;
+        let mut max_length = 0;
+        for len in 1..=MAX_CODE_LENGTH {
+            code <<= 1;
+            first_code[len] = code;
+            first_index[len] = index;
+            code += counts[len];
+            index += counts[len];
+            // More codes of this length than there are bit patterns left.
+            if code > 1 << len {
+                return Err(Error::InvalidHuffmanTable);
+            }
+            if counts[len] > 0 {
+                max_length = len as u32;
+            }
+        }
+
+        let lookup_bits = max_length.clamp(1, LOOKUP_BITS);
+        let mut lookup = vec![0u32; 1 << lookup_bits];
+        for len in 1..=lookup_bits as usize {
+            for idx in 0..counts[len] {
+                let symbol = symbols[(first_index[len] + idx) as usize] as u32;
+                // Every lookup index which starts with this code.
+                let shift = lookup_bits - len as u32;
+                let start = ((first_code[len] + idx) << shift) as usize;
+                let entry = (symbol << 8) | len as u32;
+                lookup[start..start + (1 << shift)].fill(entry);
+            }
+        }
+
+        Ok(Self {
+            lookup,
+            lookup_bits,
+            max_length,
+            first_code,
+            count: *counts,
+            first_index,
+            symbols,
+        })
+    }
+
+    /// Returns the length of the longest code in the table
+    pub fn max_code_length(&self) -> u8 {
+        self.max_length as u8
+    }
+
+    /// Decodes the next symbol from the reader.
+    /// Returns `Error::InvalidHuffmanCode` if the next bits are not a code of this table.
+    #[inline]
+    pub fn decode<R: Read + BinSeek>(&self, reader: &mut BitReader<R>) -> Result<u16> {
+        if self.max_length == 0 {
+            return Err(Error::InvalidHuffmanCode);
+        }
+        let (bits, available) = reader.peek_bits_padded(self.max_length)?;
+        let entry = self.lookup[(bits >> (self.max_length - self.lookup_bits)) as usize];
+        let (symbol, len) = if entry & 0xFF != 0 {
+            ((entry >> 8) as u16, entry & 0xFF)
+        } else {
+            self.decode_long(bits)?
+        };
+        if len > available {
+            return Err(Error::File(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "end of file",
+            )));
+        }
+        reader.consume_bits(len);
+        Ok(symbol)
+    }
+
+    /// Canonical search for codes longer than the lookup table.
+    #[cold]
+    fn decode_long(&self, bits: u64) -> Result<(u16, u32)> {
+        for len in self.lookup_bits + 1..=self.max_length {
+            let code = (bits >> (self.max_length - len)) as u32;
+            let offset = code.wrapping_sub(self.first_code[len as usize]);
+            if offset < self.count[len as usize] {
+                let index = self.first_index[len as usize] + offset;
+                return Ok((self.symbols[index as usize], len));
+            }
+        }
+        Err(Error::InvalidHuffmanCode)
+    }
+}
+
+/// Counts the codes of each length.
+fn count_codes(lengths: &[u8]) -> [u32; MAX_CODE_LENGTH + 1] {
+    let mut counts = [0u32; MAX_CODE_LENGTH + 1];
+    for &len in lengths.iter().filter(|&&len| len > 0) {
+        counts[len as usize] += 1;
+    }
+    counts
+}
@@ -0,0 +1,529 @@
+use std::io::{self, Read};
+
+use crate::{
+    bin_error::{Error, Result},
+    BinMemoryBuffer, BinStream, BitReader, HuffmanTable,
+};
+
+/// Back-references reach at most this far into the decompressed data.
+const WINDOW_SIZE: usize = 32 * 1024;
+
+/// Decoding stops once this much decompressed data is waiting to be read.
+const OUT_CHUNK: usize = 32 * 1024;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order in which the code length code lengths are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const END_OF_BLOCK: u16 = 256;
+
+/// ReversedBits reverses the bit order of every byte read from the underlying reader.
+///
+/// DEFLATE packs bits least significant bit first, `BitReader` reads them most significant
+/// bit first. Over reversed bytes, Huffman codes (stored MSB first) read out as they are and
+/// other fields only need their `n` bits reversed.
+#[derive(Debug)]
+struct ReversedBits<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> Read for ReversedBits<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let len = self.reader.read(buffer)?;
+        for byte in buffer[..len].iter_mut() {
+            *byte = byte.reverse_bits();
+        }
+        Ok(len)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Raw,
+    Zlib,
+    Gzip,
+}
+
+enum State {
+    Header,
+    BlockHeader,
+    Stored(usize),
+    Codes(Box<(HuffmanTable, HuffmanTable)>),
+    Trailer,
+    Done,
+    Failed,
+}
+
+/// InflateReader decompresses a raw DEFLATE (RFC 1951), zlib (RFC 1950) or gzip (RFC 1952)
+/// stream and exposes the decompressed data as a `Read` source.
+///
+/// zlib and gzip checksums are verified at the end of the stream, a mismatch is reported as
+/// `Error::ChecksumMismatch`. Concatenated gzip members are decompressed one after the other.
+pub struct InflateReader<R: Read> {
+    bits: BitReader<BinStream<ReversedBits<R>>>,
+    format: Format,
+    state: State,
+    last_block: bool,
+    // Decompressed data: up to `WINDOW_SIZE` bytes of history followed by unread data.
+    out: Vec<u8>,
+    read_pos: usize,
+    // Bytes of `out` before this index are included in the checksum.
+    checked: usize,
+    // Number of bytes decompressed in the current zlib stream or gzip member.
+    member_out: u64,
+    adler: Adler32,
+    crc: Crc32,
+}
+
+impl<R: Read> InflateReader<R> {
+    /// Creates a reader for a raw DEFLATE stream, without header or checksum
+    pub fn new(reader: R) -> Self {
+        Self::with_format(reader, Format::Raw)
+    }
+
+    /// Creates a reader for a zlib stream, the Adler-32 checksum is verified
+    pub fn zlib(reader: R) -> Self {
+        Self::with_format(reader, Format::Zlib)
+    }
+
+    /// Creates a reader for a gzip stream, the CRC-32 and size of every member are verified
+    pub fn gzip(reader: R) -> Self {
+        Self::with_format(reader, Format::Gzip)
+    }
+
+    fn with_format(reader: R, format: Format) -> Self {
+        Self {
+            bits: BitReader::forward_only(ReversedBits { reader }),
+            format,
+            state: State::Header,
+            last_block: false,
+            out: Vec::new(),
+            read_pos: 0,
+            checked: 0,
+            member_out: 0,
+            adler: Adler32::new(),
+            crc: Crc32::new(),
+        }
+    }
+
+    /// Returns the number of compressed bytes consumed so far.
+    /// The underlying reader may have been read further ahead.
+    pub fn total_in(&self) -> u64 {
+        self.bits.bit_cursor().div_ceil(8)
+    }
+
+    /// Consumes the `InflateReader` and returns the underlying reader.
+    /// Compressed data read ahead of `total_in` is lost.
+    pub fn into_inner(self) -> R {
+        self.bits.into_inner().into_inner().reader
+    }
+
+    /// Decompresses the rest of the stream into a `BinMemoryBuffer`, for random access
+    pub fn into_memory_buffer(mut self) -> Result<BinMemoryBuffer> {
+        let mut buffer = Vec::new();
+        self.read_to_end(&mut buffer)?;
+        Ok(BinMemoryBuffer::from(buffer))
+    }
+
+    /// Reads `num_bits` (up to 32) of a field stored least significant bit first.
+    #[inline]
+    fn read_lsb(&mut self, num_bits: u8) -> Result<u32> {
+        if num_bits == 0 {
+            return Ok(0);
+        }
+        let value = self.bits.read_bits(num_bits)? as u32;
+        Ok(value.reverse_bits() >> (32 - num_bits as u32))
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_lsb(8)? as u8)
+    }
+
+    fn read_u16_le(&mut self) -> Result<u16> {
+        Ok(self.read_lsb(16)? as u16)
+    }
+
+    fn read_u32_le(&mut self) -> Result<u32> {
+        self.read_lsb(32)
+    }
+
+    fn read_header(&mut self) -> Result<()> {
+        self.member_out = 0;
+        self.last_block = false;
+        match self.format {
+            Format::Raw => {}
+            Format::Zlib => {
+                self.adler = Adler32::new();
+                let cmf = self.read_u8()?;
+                let flg = self.read_u8()?;
+                if cmf & 0x0F != 8 || cmf >> 4 > 7 {
+                    return Err(Error::InvalidDeflateData(
+                        "unsupported zlib compression method",
+                    ));
+                }
+                if !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+                    return Err(Error::InvalidDeflateData("zlib header check failed"));
+                }
+                if flg & 0x20 != 0 {
+                    return Err(Error::InvalidDeflateData("zlib preset dictionary"));
+                }
+            }
+            Format::Gzip => {
+                self.crc = Crc32::new();
+                if self.read_u8()? != 0x1F || self.read_u8()? != 0x8B {
+                    return Err(Error::InvalidDeflateData("not a gzip stream"));
+                }
+                if self.read_u8()? != 8 {
+                    return Err(Error::InvalidDeflateData(
+                        "unsupported gzip compression method",
+                    ));
+                }
+                let flags = self.read_u8()?;
+                // MTIME, XFL and OS
+                self.bits.skip_bits(6 * 8)?;
+                if flags & 0x04 != 0 {
+                    let extra_len = self.read_u16_le()? as usize;
+                    self.bits.skip_bits(extra_len * 8)?;
+                }
+                // File name and comment are zero terminated.
+                for flag in [0x08, 0x10] {
+                    if flags & flag != 0 {
+                        while self.read_u8()? != 0 {}
+                    }
+                }
+                if flags & 0x02 != 0 {
+                    self.read_u16_le()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_block_header(&mut self) -> Result<State> {
+        self.last_block = self.read_lsb(1)? == 1;
+        match self.read_lsb(2)? {
+            0 => {
+                self.bits.byte_align();
+                let len = self.read_u16_le()?;
+                let nlen = self.read_u16_le()?;
+                if len != !nlen {
+                    return Err(Error::InvalidDeflateData("stored block length mismatch"));
+                }
+                Ok(State::Stored(len as usize))
+            }
+            1 => Ok(State::Codes(Box::new(fixed_tables()?))),
+            2 => Ok(State::Codes(Box::new(self.read_dynamic_tables()?))),
+            _ => Err(Error::InvalidDeflateData("invalid block type")),
+        }
+    }
+
+    fn read_dynamic_tables(&mut self) -> Result<(HuffmanTable, HuffmanTable)> {
+        let num_literals = self.read_lsb(5)? as usize + 257;
+        let num_distances = self.read_lsb(5)? as usize + 1;
+        let num_code_lengths = self.read_lsb(4)? as usize + 4;
+        if num_literals > 286 || num_distances > 30 {
+            return Err(Error::InvalidDeflateData(
+                "too many length or distance codes",
+            ));
+        }
+
+        let mut code_length_lengths = [0u8; 19];
+        for &idx in CODE_LENGTH_ORDER.iter().take(num_code_lengths) {
+            code_length_lengths[idx] = self.read_lsb(3)? as u8;
+        }
+        let code_length_table = HuffmanTable::from_lengths(&code_length_lengths)?;
+
+        let total = num_literals + num_distances;
+        let mut lengths = Vec::with_capacity(total);
+        while lengths.len() < total {
+            let (value, repeat) = match code_length_table.decode(&mut self.bits)? {
+                len @ 0..=15 => (len as u8, 1),
+                16 => match lengths.last() {
+                    Some(&previous) => (previous, 3 + self.read_lsb(2)? as usize),
+                    None => return Err(Error::InvalidDeflateData("repeat without a length")),
+                },
+                17 => (0, 3 + self.read_lsb(3)? as usize),
+                _ => (0, 11 + self.read_lsb(7)? as usize),
+            };
+            if lengths.len() + repeat > total {
+                return Err(Error::InvalidDeflateData("code lengths overflow"));
+            }
+            lengths.resize(lengths.len() + repeat, value);
+        }
+        if lengths[END_OF_BLOCK as usize] == 0 {
+            return Err(Error::InvalidDeflateData("missing end-of-block code"));
+        }
+
+        Ok((
+            HuffmanTable::from_lengths(&lengths[..num_literals])?,
+            HuffmanTable::from_lengths(&lengths[num_literals..])?,
+        ))
+    }
+
+    /// Copies up to `remaining` bytes of a stored block, returns how many are left.
+    fn copy_stored(&mut self, remaining: usize) -> Result<usize> {
+        let start = self.out.len();
+        let len = remaining.min(OUT_CHUNK);
+        self.out.resize(start + len, 0);
+        self.bits.read_exact(&mut self.out[start..])?;
+        for byte in self.out[start..].iter_mut() {
+            *byte = byte.reverse_bits();
+        }
+        self.member_out += len as u64;
+        Ok(remaining - len)
+    }
+
+    /// Decodes symbols until the end of the block or until enough output is waiting.
+    /// Returns true at the end of the block.
+    fn decode_codes(&mut self, literals: &HuffmanTable, distances: &HuffmanTable) -> Result<bool> {
+        while self.out.len() - self.read_pos < OUT_CHUNK {
+            let symbol = literals.decode(&mut self.bits)?;
+            if symbol < END_OF_BLOCK {
+                self.out.push(symbol as u8);
+                self.member_out += 1;
+                continue;
+            }
+            if symbol == END_OF_BLOCK {
+                return Ok(true);
+            }
+
+            let idx = (symbol - 257) as usize;
+            if idx >= LENGTH_BASE.len() {
+                return Err(Error::InvalidDeflateData("invalid length code"));
+            }
+            let len = LENGTH_BASE[idx] as usize + self.read_lsb(LENGTH_EXTRA[idx])? as usize;
+            let idx = distances.decode(&mut self.bits)? as usize;
+            if idx >= DISTANCE_BASE.len() {
+                return Err(Error::InvalidDeflateData("invalid distance code"));
+            }
+            let distance =
+                DISTANCE_BASE[idx] as usize + self.read_lsb(DISTANCE_EXTRA[idx])? as usize;
+            if distance as u64 > self.member_out || distance > self.out.len() {
+                return Err(Error::InvalidDeflateData("distance too far back"));
+            }
+
+            let start = self.out.len() - distance;
+            if distance >= len {
+                self.out.extend_from_within(start..start + len);
+            } else {
+                // Overlapping copy repeats the last `distance` bytes.
+                for idx in start..start + len {
+                    let byte = self.out[idx];
+                    self.out.push(byte);
+                }
+            }
+            self.member_out += len as u64;
+        }
+        Ok(false)
+    }
+
+    fn check_trailer(&mut self) -> Result<()> {
+        self.bits.byte_align();
+        match self.format {
+            Format::Raw => {}
+            Format::Zlib => {
+                let expected = self.read_u32_le()?.swap_bytes();
+                if expected != self.adler.value() {
+                    return Err(Error::ChecksumMismatch);
+                }
+            }
+            Format::Gzip => {
+                let crc = self.read_u32_le()?;
+                let size = self.read_u32_le()?;
+                if crc != self.crc.value() || size != self.member_out as u32 {
+                    return Err(Error::ChecksumMismatch);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the next two bytes are the gzip magic number.
+    fn next_is_gzip_member(&mut self) -> Result<bool> {
+        // The bytes are bit reversed, 0x1F 0x8B reads as 0xF8 0xD1.
+        let (bits, available) = self.bits.peek_bits_padded(16)?;
+        Ok(available == 16 && bits == 0xF8D1)
+    }
+
+    /// Adds the data decompressed since the last call to the checksum of the current member.
+    fn update_checksums(&mut self) {
+        let data = &self.out[self.checked..];
+        match self.format {
+            Format::Raw => {}
+            Format::Zlib => self.adler.update(data),
+            Format::Gzip => self.crc.update(data),
+        }
+        self.checked = self.out.len();
+    }
+
+    /// Runs the decoder until enough output is waiting to be read or the stream is done.
+    fn decode_some(&mut self) -> Result<()> {
+        while self.out.len() - self.read_pos < OUT_CHUNK {
+            // The state stays `Failed` if decoding returns an error.
+            self.state = match std::mem::replace(&mut self.state, State::Failed) {
+                State::Header => {
+                    self.read_header()?;
+                    State::BlockHeader
+                }
+                State::BlockHeader if self.last_block => State::Trailer,
+                State::BlockHeader => self.read_block_header()?,
+                State::Stored(remaining) => match self.copy_stored(remaining)? {
+                    0 => State::BlockHeader,
+                    left => State::Stored(left),
+                },
+                State::Codes(tables) => {
+                    if self.decode_codes(&tables.0, &tables.1)? {
+                        State::BlockHeader
+                    } else {
+                        State::Codes(tables)
+                    }
+                }
+                State::Trailer => {
+                    self.update_checksums();
+                    self.check_trailer()?;
+                    // Another gzip member may follow, anything else after the trailer is ignored.
+                    if self.format == Format::Gzip && self.next_is_gzip_member()? {
+                        State::Header
+                    } else {
+                        State::Done
+                    }
+                }
+                State::Done => {
+                    self.state = State::Done;
+                    break;
+                }
+                State::Failed => {
+                    return Err(Error::InvalidDeflateData("stream failed to decode earlier"))
+                }
+            };
+        }
+        self.update_checksums();
+        Ok(())
+    }
+}
+
+/// Implement the `Read` trait for `InflateReader`, handing out the decompressed data.
+impl<R: Read> Read for InflateReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.read_pos == self.out.len() {
+            self.decode_some()?;
+        }
+        let len = buffer.len().min(self.out.len() - self.read_pos);
+        buffer[..len].copy_from_slice(&self.out[self.read_pos..self.read_pos + len]);
+        self.read_pos += len;
+
+        // Keep only the history back-references can reach.
+        if self.read_pos > 2 * WINDOW_SIZE + OUT_CHUNK {
+            let drop = self.read_pos - WINDOW_SIZE;
+            self.out.drain(..drop);
+            self.read_pos -= drop;
+            self.checked -= drop;
+        }
+        Ok(len)
+    }
+}
+
+/// Builds the literal/length and distance tables of fixed Huffman blocks.
+fn fixed_tables() -> Result<(HuffmanTable, HuffmanTable)> {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((
+        HuffmanTable::from_lengths(&lengths)?,
+        HuffmanTable::from_lengths(&[5; 32])?,
+    ))
+}
+
+/// Adler-32 checksum used by zlib.
+#[derive(Debug, Clone, Copy)]
+struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    const MOD: u32 = 65521;
+    // The most bytes which can be summed before `b` may overflow.
+    const NMAX: usize = 5552;
+
+    fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(Self::NMAX) {
+            for &byte in chunk {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= Self::MOD;
+            self.b %= Self::MOD;
+        }
+    }
+
+    fn value(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+/// CRC-32 (IEEE 802.3) checksum used by gzip.
+#[derive(Debug, Clone, Copy)]
+struct Crc32 {
+    crc: u32,
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut idx = 0;
+    while idx < 256 {
+        let mut crc = idx as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[idx] = crc;
+        idx += 1;
+    }
+    table
+}
+
+impl Crc32 {
+    fn new() -> Self {
+        Self { crc: 0xFFFF_FFFF }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.crc = CRC32_TABLE[((self.crc ^ byte as u32) & 0xFF) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    fn value(&self) -> u32 {
+        !self.crc
+    }
+}
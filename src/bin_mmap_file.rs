@@ -0,0 +1,88 @@
+use std::{fs::File, io::Read, ops::Range};
+
+use memmap2::Mmap;
+
+use crate::{
+    bin_error::{Error, Result},
+    BinSeek,
+};
+
+/// BinMmapFile reads a file through a read-only memory map, so data comes from the OS page cache
+/// without read syscalls and ranges can be borrowed without copying.
+///
+/// The file must not be truncated or modified by anyone while it is mapped.
+#[derive(Debug)]
+pub struct BinMmapFile {
+    mmap: Mmap,
+    position: usize,
+}
+
+impl BinMmapFile {
+    // Maps the whole `file` into memory
+    pub(crate) fn new(file: &File) -> Result<Self> {
+        // SAFETY: the mapping is read-only, changes of the file while it is mapped are
+        // documented as not allowed.
+        let mmap = unsafe { Mmap::map(file)? };
+        Ok(Self { mmap, position: 0 })
+    }
+
+    /// Returns the whole file as a slice
+    pub fn as_slice(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Returns a slice of the remaining data, starting from the current position
+    pub fn remaining_slice(&self) -> &[u8] {
+        &self.mmap[self.position.min(self.mmap.len())..]
+    }
+
+    /// Returns the bytes in `range` without copying them, the position is not changed
+    pub fn slice(&self, range: Range<usize>) -> Result<&[u8]> {
+        if range.start > range.end || range.end > self.mmap.len() {
+            return Err(Error::File(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "end of file",
+            )));
+        }
+        Ok(&self.mmap[range])
+    }
+
+    /// Returns the next `len` bytes without copying them and moves the position past them
+    pub fn read_slice(&mut self, len: usize) -> Result<&[u8]> {
+        let start = self.position;
+        let end = start.saturating_add(len);
+        self.slice(start..end)?;
+        self.position = end;
+        Ok(&self.mmap[start..end])
+    }
+}
+
+impl BinSeek for BinMmapFile {
+    fn seek(&mut self, to: usize) -> Result<usize> {
+        self.position = to;
+        Ok(self.position)
+    }
+
+    fn pos(&mut self) -> Result<usize> {
+        Ok(self.position)
+    }
+
+    fn len(&self) -> Result<usize> {
+        Ok(self.mmap.len())
+    }
+}
+
+/// Implement the `Read` trait for `BinMmapFile`, copying from the mapped memory.
+impl Read for BinMmapFile {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let len = Read::read(&mut self.remaining_slice(), buffer)?;
+        self.position += len;
+        Ok(len)
+    }
+
+    fn read_exact(&mut self, buffer: &mut [u8]) -> std::io::Result<()> {
+        Read::read_exact(&mut self.remaining_slice(), buffer)?;
+        self.position += buffer.len();
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+use std::io::{self, Read};
+
+use crate::{bin_error::Result, BinSeek, BinStream, BitReader};
+
+const DEFAULT_BUF_SIZE: usize = 64 * 1024;
+
+/// RbspReader removes H.264/HEVC emulation-prevention bytes (the `0x03` in `0x00 0x00 0x03`)
+/// on the fly, turning a NAL unit payload into its raw byte sequence payload (RBSP).
+///
+/// The raw offsets of the removed bytes are kept, so positions in the RBSP can be mapped
+/// back to positions in the NAL unit with `raw_offset`.
+#[derive(Debug)]
+pub struct RbspReader<R: Read> {
+    reader: R,
+    // Number of zero bytes directly before the next raw byte, saturating at 2.
+    zeros: u8,
+    // Number of raw bytes consumed from the underlying reader.
+    raw_pos: u64,
+    // Raw offsets of the removed emulation-prevention bytes.
+    removed: Vec<u64>,
+}
+
+impl<R: Read> RbspReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            zeros: 0,
+            raw_pos: 0,
+            removed: Vec::new(),
+        }
+    }
+
+    /// Returns the raw offsets (relative to the start of the reader) of the
+    /// emulation-prevention bytes removed so far
+    pub fn emulation_prevention_offsets(&self) -> &[u64] {
+        &self.removed
+    }
+
+    /// Maps an offset in the RBSP to the offset of the same byte in the raw data.
+    /// Only valid for offsets which have already been read.
+    pub fn raw_offset(&self, rbsp_offset: u64) -> u64 {
+        // The i-th removed byte sat in front of RBSP byte `removed[i] - i`, binary search
+        // for the number of removed bytes in front of `rbsp_offset`.
+        let (mut low, mut high) = (0, self.removed.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.removed[mid] - mid as u64 <= rbsp_offset {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        rbsp_offset + low as u64
+    }
+
+    /// Consumes the `RbspReader` and returns the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Implement the `Read` trait for `RbspReader`, the emulation-prevention bytes are left out.
+impl<R: Read> Read for RbspReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let len = self.reader.read(buffer)?;
+            if len == 0 {
+                return Ok(0);
+            }
+            let mut out = 0;
+            for i in 0..len {
+                let byte = buffer[i];
+                if self.zeros == 2 && byte == 0x03 {
+                    self.removed.push(self.raw_pos + i as u64);
+                    self.zeros = 0;
+                    continue;
+                }
+                self.zeros = if byte == 0 {
+                    (self.zeros + 1).min(2)
+                } else {
+                    0
+                };
+                buffer[out] = byte;
+                out += 1;
+            }
+            self.raw_pos += len as u64;
+            // A chunk made only of emulation-prevention bytes is not the end of the data.
+            if out > 0 {
+                return Ok(out);
+            }
+        }
+    }
+}
+
+/// A single NAL unit found by `AnnexBReader`, without its start code and trailing zero bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NalUnit {
+    offset: usize,
+    data: Vec<u8>,
+}
+
+impl NalUnit {
+    /// Returns the offset in the source of the first byte of the NAL unit (its header)
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the raw NAL unit bytes, including the header and emulation-prevention bytes
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the H.264 `nal_unit_type` from the one byte NAL unit header
+    pub fn h264_type(&self) -> u8 {
+        self.data.first().map_or(0, |header| header & 0x1F)
+    }
+
+    /// Returns the HEVC `nal_unit_type` from the two byte NAL unit header
+    pub fn hevc_type(&self) -> u8 {
+        self.data.first().map_or(0, |header| (header >> 1) & 0x3F)
+    }
+
+    /// Returns a reader over the RBSP of the whole NAL unit (header included)
+    pub fn rbsp_reader(&self) -> RbspReader<&[u8]> {
+        RbspReader::new(&self.data)
+    }
+
+    /// Returns a forward-only `BitReader` over the RBSP of the whole NAL unit (header included)
+    pub fn bit_reader(&self) -> BitReader<BinStream<RbspReader<&[u8]>>> {
+        BitReader::forward_only(self.rbsp_reader())
+    }
+
+    /// Consumes the `NalUnit` and returns the raw bytes
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// AnnexBReader splits an Annex-B byte stream (`0x000001` / `0x00000001` start codes)
+/// into NAL units. It reads forward from the current position of the source.
+pub struct AnnexBReader<R: Read + BinSeek> {
+    reader: R,
+    buffer: Vec<u8>,
+    pos: usize,
+    cap: usize,
+    // Source offset of `buffer[pos]`.
+    offset: usize,
+    // True once the first start code was found.
+    started: bool,
+}
+
+impl<R: Read + BinSeek> AnnexBReader<R> {
+    pub fn new(mut reader: R) -> Self {
+        let offset = reader.pos().unwrap_or(0);
+        Self {
+            reader,
+            buffer: vec![0; DEFAULT_BUF_SIZE],
+            pos: 0,
+            cap: 0,
+            offset,
+            started: false,
+        }
+    }
+
+    /// Consumes the `AnnexBReader` and returns the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Makes sure there is unread data in the buffer, returns false at the end of the source.
+    fn fill_buf(&mut self) -> Result<bool> {
+        if self.pos < self.cap {
+            return Ok(true);
+        }
+        self.pos = 0;
+        self.cap = loop {
+            match self.reader.read(&mut self.buffer) {
+                Ok(len) => break len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        };
+        Ok(self.cap > 0)
+    }
+
+    /// Appends bytes to `data` up to and including the next start code.
+    /// Returns false if the source ended first.
+    fn read_until_start_code(&mut self, data: &mut Vec<u8>) -> Result<bool> {
+        while self.fill_buf()? {
+            let chunk = &self.buffer[self.pos..self.cap];
+            let (take, found) = match chunk.iter().position(|&b| b == 0x01) {
+                Some(idx) => (idx + 1, true),
+                None => (chunk.len(), false),
+            };
+            data.extend_from_slice(&chunk[..take]);
+            self.pos += take;
+            self.offset += take;
+            if found && data.len() >= 3 && data[data.len() - 3..data.len() - 1] == [0, 0] {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the next NAL unit, or `None` at the end of the source
+    pub fn next_unit(&mut self) -> Result<Option<NalUnit>> {
+        let mut data = Vec::new();
+        if !self.started {
+            // Skip anything in front of the first start code.
+            if !self.read_until_start_code(&mut data)? {
+                return Ok(None);
+            }
+            self.started = true;
+        }
+        loop {
+            data.clear();
+            let offset = self.offset;
+            let found = self.read_until_start_code(&mut data)?;
+            if found {
+                // Drop the start code prefix.
+                data.truncate(data.len() - 3);
+            }
+            // Trailing zero bytes belong to the next start code or are padding.
+            let len = data.iter().rposition(|&b| b != 0).map_or(0, |idx| idx + 1);
+            data.truncate(len);
+            if !data.is_empty() {
+                return Ok(Some(NalUnit { offset, data }));
+            }
+            if !found {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+impl<R: Read + BinSeek> Iterator for AnnexBReader<R> {
+    type Item = Result<NalUnit>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_unit().transpose()
+    }
+}
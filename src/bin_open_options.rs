@@ -0,0 +1,107 @@
+use std::{fs::OpenOptions, io, path::Path};
+
+use crate::{
+    bin_error::{Error, Result},
+    BinFile, FileLock,
+};
+
+/// Options which configure how a `BinFile` is opened, created with `BinFile::options`.
+///
+/// The options mirror `std::fs::OpenOptions`. Nothing is enabled by default,
+/// so at least one of `read`, `write` or `append` has to be set.
+#[derive(Debug, Clone)]
+pub struct BinOpenOptions {
+    options: OpenOptions,
+    append: bool,
+    buffer_capacity: usize,
+    lock: Option<(FileLock, bool)>,
+}
+
+impl BinOpenOptions {
+    pub(crate) fn new() -> Self {
+        Self {
+            options: OpenOptions::new(),
+            append: false,
+            buffer_capacity: 0,
+            lock: None,
+        }
+    }
+
+    /// Sets the option for read access.
+    pub fn read(&mut self, read: bool) -> &mut Self {
+        self.options.read(read);
+        self
+    }
+
+    /// Sets the option for write access, writes overwrite the data at the current position.
+    pub fn write(&mut self, write: bool) -> &mut Self {
+        self.options.write(write);
+        self
+    }
+
+    /// Sets the option for append mode, every write goes to the end of the file.
+    pub fn append(&mut self, append: bool) -> &mut Self {
+        self.options.append(append);
+        self.append = append;
+        self
+    }
+
+    /// Sets the option to truncate an existing file to length 0, requires write access.
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.options.truncate(truncate);
+        self
+    }
+
+    /// Sets the option to create the file if it does not exist.
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.options.create(create);
+        self
+    }
+
+    /// Sets the option to create a new file, failing if it already exists.
+    pub fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.options.create_new(create_new);
+        self
+    }
+
+    /// Sets the size of the read and write buffer, 0 (the default) opens an unbuffered file.
+    /// See `BinFile::buffered`.
+    pub fn buffered(&mut self, capacity: usize) -> &mut Self {
+        self.buffer_capacity = capacity;
+        self
+    }
+
+    /// Acquires an advisory lock of the kind `lock` when the file is opened, waiting for it.
+    /// See `BinFile::lock`.
+    pub fn lock(&mut self, lock: FileLock) -> &mut Self {
+        self.lock = Some((lock, true));
+        self
+    }
+
+    /// Acquires an advisory lock of the kind `lock` when the file is opened, opening fails
+    /// with `ErrorKind::WouldBlock` if it is held by someone else.
+    pub fn try_lock(&mut self, lock: FileLock) -> &mut Self {
+        self.lock = Some((lock, false));
+        self
+    }
+
+    /// Opens the file at `path` with these options.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<BinFile> {
+        let file = self.options.open(path)?;
+        let bin_file = match self.append {
+            true => BinFile::new_append(file)?,
+            false => BinFile::new(file)?,
+        };
+        match self.lock {
+            Some((lock, true)) => bin_file.lock(lock)?,
+            Some((lock, false)) if !bin_file.try_lock_as(lock)? => {
+                return Err(Error::File(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "file is locked",
+                )));
+            }
+            _ => {}
+        }
+        bin_file.buffered(self.buffer_capacity)
+    }
+}
@@ -0,0 +1,212 @@
+use crate::bin_error::{Error, Result};
+
+/// The layout of samples packed back to back into bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// Each sample is stored most significant bit first, continuing into the next byte.
+    MsbFirst,
+    /// Each sample starts at the lowest free bit, as in a little-endian bit stream.
+    LsbFirst,
+    /// MIPI CSI-2 RAW10/RAW12/RAW14 grouping: the 8 most significant bits of every sample
+    /// of a group come first, followed by the packed least significant bits.
+    Mipi,
+}
+
+impl BitOrder {
+    /// Returns the number of samples and bytes of a packing group,
+    /// the smallest run of samples that ends on a byte boundary.
+    pub(crate) fn group(&self, bits_per_sample: u8) -> Result<(usize, usize)> {
+        if bits_per_sample == 0 || bits_per_sample > 16 {
+            return Err(Error::NotSupported);
+        }
+        let samples = match self {
+            BitOrder::Mipi => match bits_per_sample {
+                10 | 14 => 4,
+                12 => 2,
+                _ => return Err(Error::NotSupported),
+            },
+            _ => 8 / gcd(bits_per_sample as usize, 8),
+        };
+        Ok((samples, samples * bits_per_sample as usize / 8))
+    }
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Returns the number of bytes `count` samples occupy, a trailing partial byte included.
+pub(crate) fn packed_len(count: usize, bits_per_sample: u8) -> usize {
+    (count * bits_per_sample as usize).div_ceil(8)
+}
+
+/// Unpacks `out.len()` samples from `bytes`, which must hold at least `packed_len` bytes.
+/// `Mipi` needs a whole number of groups.
+pub(crate) fn unpack(bytes: &[u8], out: &mut [u16], bits_per_sample: u8, order: BitOrder) {
+    match (order, bits_per_sample) {
+        (BitOrder::MsbFirst, 10) => unpack_groups(bytes, out, 5, 4, |b, s| {
+            s[0] = (b[0] as u16) << 2 | (b[1] >> 6) as u16;
+            s[1] = ((b[1] & 0x3F) as u16) << 4 | (b[2] >> 4) as u16;
+            s[2] = ((b[2] & 0x0F) as u16) << 6 | (b[3] >> 2) as u16;
+            s[3] = ((b[3] & 0x03) as u16) << 8 | b[4] as u16;
+        }),
+        (BitOrder::MsbFirst, 12) => unpack_groups(bytes, out, 3, 2, |b, s| {
+            s[0] = (b[0] as u16) << 4 | (b[1] >> 4) as u16;
+            s[1] = ((b[1] & 0x0F) as u16) << 8 | b[2] as u16;
+        }),
+        (BitOrder::LsbFirst, 10) => unpack_groups(bytes, out, 5, 4, |b, s| {
+            let v = u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], 0, 0, 0]);
+            for (idx, sample) in s.iter_mut().enumerate() {
+                *sample = (v >> (10 * idx)) as u16 & 0x3FF;
+            }
+        }),
+        (BitOrder::LsbFirst, 12) => unpack_groups(bytes, out, 3, 2, |b, s| {
+            s[0] = b[0] as u16 | ((b[1] & 0x0F) as u16) << 8;
+            s[1] = (b[1] >> 4) as u16 | (b[2] as u16) << 4;
+        }),
+        (BitOrder::Mipi, 10) => unpack_groups(bytes, out, 5, 4, |b, s| {
+            for (idx, sample) in s.iter_mut().enumerate() {
+                *sample = (b[idx] as u16) << 2 | ((b[4] >> (2 * idx)) & 0x03) as u16;
+            }
+        }),
+        (BitOrder::Mipi, 12) => unpack_groups(bytes, out, 3, 2, |b, s| {
+            s[0] = (b[0] as u16) << 4 | (b[2] & 0x0F) as u16;
+            s[1] = (b[1] as u16) << 4 | (b[2] >> 4) as u16;
+        }),
+        (BitOrder::Mipi, _) => unpack_groups(bytes, out, 7, 4, |b, s| {
+            let low = u32::from_le_bytes([b[4], b[5], b[6], 0]);
+            for (idx, sample) in s.iter_mut().enumerate() {
+                *sample = (b[idx] as u16) << 6 | ((low >> (6 * idx)) & 0x3F) as u16;
+            }
+        }),
+        (BitOrder::MsbFirst, bits) => {
+            let mask = (1u32 << bits) - 1;
+            let (mut acc, mut acc_bits) = (0u32, 0u32);
+            let mut bytes = bytes.iter();
+            for sample in out.iter_mut() {
+                while acc_bits < bits as u32 {
+                    acc = (acc << 8) | *bytes.next().unwrap_or(&0) as u32;
+                    acc_bits += 8;
+                }
+                acc_bits -= bits as u32;
+                *sample = ((acc >> acc_bits) & mask) as u16;
+            }
+        }
+        (BitOrder::LsbFirst, bits) => {
+            let mask = (1u32 << bits) - 1;
+            let (mut acc, mut acc_bits) = (0u32, 0u32);
+            let mut bytes = bytes.iter();
+            for sample in out.iter_mut() {
+                while acc_bits < bits as u32 {
+                    acc |= (*bytes.next().unwrap_or(&0) as u32) << acc_bits;
+                    acc_bits += 8;
+                }
+                *sample = (acc & mask) as u16;
+                acc >>= bits;
+                acc_bits -= bits as u32;
+            }
+        }
+    }
+}
+
+/// Unpacks whole groups with `f`, the samples of a trailing partial group are
+/// unpacked from a zero padded copy.
+#[inline(always)]
+fn unpack_groups<F: Fn(&[u8], &mut [u16])>(
+    bytes: &[u8],
+    out: &mut [u16],
+    group_bytes: usize,
+    group_samples: usize,
+    f: F,
+) {
+    let start = (out.len() / group_samples) * group_bytes;
+    let mut out_groups = out.chunks_exact_mut(group_samples);
+    for (b, s) in bytes.chunks_exact(group_bytes).zip(&mut out_groups) {
+        f(b, s);
+    }
+    let rest = out_groups.into_remainder();
+    if !rest.is_empty() {
+        let mut padded = [0u8; 8];
+        let tail = &bytes[start.min(bytes.len())..];
+        let len = tail.len().min(group_bytes);
+        padded[..len].copy_from_slice(&tail[..len]);
+        let mut samples = [0u16; 8];
+        f(&padded[..group_bytes], &mut samples[..group_samples]);
+        let len = rest.len();
+        rest.copy_from_slice(&samples[..len]);
+    }
+}
+
+/// Packs `samples` into `out`, only the lowest `bits_per_sample` bits of every sample are
+/// used. A trailing partial byte is padded with zero bits. `Mipi` needs a whole number of groups.
+pub(crate) fn pack(samples: &[u16], out: &mut Vec<u8>, bits_per_sample: u8, order: BitOrder) {
+    let mask = ((1u32 << bits_per_sample) - 1) as u16;
+    let start = out.len();
+    out.reserve(packed_len(samples.len(), bits_per_sample));
+    match (order, bits_per_sample) {
+        (BitOrder::MsbFirst, 12) | (BitOrder::LsbFirst, 12) | (BitOrder::Mipi, 12) => {
+            for pair in samples.chunks(2) {
+                let s0 = pair[0] & mask;
+                let s1 = pair.get(1).map_or(0, |s| s & mask);
+                let group = match order {
+                    BitOrder::MsbFirst => [(s0 >> 4) as u8, (s0 << 4 | s1 >> 8) as u8, s1 as u8],
+                    BitOrder::LsbFirst => [s0 as u8, (s0 >> 8 | s1 << 4) as u8, (s1 >> 4) as u8],
+                    BitOrder::Mipi => [
+                        (s0 >> 4) as u8,
+                        (s1 >> 4) as u8,
+                        (s0 & 0x0F | s1 << 4) as u8,
+                    ],
+                };
+                out.extend_from_slice(&group);
+            }
+        }
+        (BitOrder::Mipi, bits) => {
+            let low_bits = bits as u32 - 8;
+            for group in samples.chunks(4) {
+                let mut low = 0u32;
+                for (idx, &sample) in group.iter().enumerate() {
+                    let sample = sample & mask;
+                    out.push((sample >> low_bits) as u8);
+                    low |= ((sample as u32) & ((1 << low_bits) - 1)) << (low_bits * idx as u32);
+                }
+                out.extend(std::iter::repeat_n(0, 4 - group.len()));
+                out.extend_from_slice(&low.to_le_bytes()[..low_bits as usize / 2]);
+            }
+        }
+        (BitOrder::MsbFirst, bits) => {
+            let (mut acc, mut acc_bits) = (0u32, 0u32);
+            for &sample in samples {
+                acc = (acc << bits) | (sample & mask) as u32;
+                acc_bits += bits as u32;
+                while acc_bits >= 8 {
+                    acc_bits -= 8;
+                    out.push((acc >> acc_bits) as u8);
+                }
+            }
+            if acc_bits > 0 {
+                out.push((acc << (8 - acc_bits)) as u8);
+            }
+        }
+        (BitOrder::LsbFirst, bits) => {
+            let (mut acc, mut acc_bits) = (0u32, 0u32);
+            for &sample in samples {
+                acc |= ((sample & mask) as u32) << acc_bits;
+                acc_bits += bits as u32;
+                while acc_bits >= 8 {
+                    out.push(acc as u8);
+                    acc >>= 8;
+                    acc_bits -= 8;
+                }
+            }
+            if acc_bits > 0 {
+                out.push(acc as u8);
+            }
+        }
+    }
+    if order != BitOrder::Mipi {
+        out.truncate(start + packed_len(samples.len(), bits_per_sample));
+    }
+}
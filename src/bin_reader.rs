@@ -1,21 +1,97 @@
 use std::{fs::File, io, path::Path};
 
-use crate::{bin_error::Result, BinFile, Error};
+#[cfg(feature = "mmap")]
+use crate::BinMmapFile;
+#[cfg(unix)]
+use crate::SharedBinFile;
+use crate::{bin_error::Result, BinChain, BinDecompressReader, BinFile, Error};
+
+/// Read buffer size for compressed files.
+const DECOMPRESS_BUF_SIZE: usize = 64 * 1024;
 
 #[derive(Debug, Default)]
 pub struct BinReader {}
 
 impl BinReader {
     /// Method to open a binary file and create a new BinFile instance from it
-    pub fn open(file_path: &str) -> Result<BinFile> {
-        let path = Path::new(file_path);
+    pub fn open<P: AsRef<Path>>(file_path: P) -> Result<BinFile> {
+        let path = file_path.as_ref();
         if !path.exists() {
             return Err(Error::File(io::Error::new(
                 io::ErrorKind::NotFound,
                 "File not Exists",
             )));
         }
-        let file = File::open(file_path)?;
+        let file = File::open(path)?;
         BinFile::new(file)
     }
+
+    /// Method to open several files as one continuous `BinChain`, in the given order
+    pub fn open_chain<I, P>(file_paths: I) -> Result<BinChain<BinFile>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let files = file_paths
+            .into_iter()
+            .map(Self::open)
+            .collect::<Result<Vec<_>>>()?;
+        BinChain::from_sources(files)
+    }
+
+    /// Method to open the numbered segments of a split file, starting with `first_path`
+    /// (e.g. `capture.000`) and continuing with `capture.001`, ... as long as they exist
+    pub fn open_segments<P: AsRef<Path>>(first_path: P) -> Result<BinChain<BinFile>> {
+        let first_path = first_path.as_ref();
+        let extension = first_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .filter(|extension| extension.bytes().all(|byte| byte.is_ascii_digit()))
+            .ok_or_else(|| {
+                Error::File(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "extension is not a segment number",
+                ))
+            })?;
+        let width = extension.len();
+        let first = extension
+            .parse::<usize>()
+            .map_err(|_| Error::NotSupported)?;
+
+        let mut chain = BinChain::new();
+        chain.push(Self::open(first_path)?)?;
+        for number in first + 1.. {
+            let path = first_path.with_extension(format!("{:0width$}", number, width = width));
+            if !path.exists() {
+                break;
+            }
+            chain.push(Self::open(path)?)?;
+        }
+        Ok(chain)
+    }
+
+    /// Method to open a binary file which may be gzip, zstd or xz compressed, picking the
+    /// format by its magic number. Files which decompress to at most `memory_limit` bytes are
+    /// decompressed into memory, larger ones while reading (see `BinDecompressReader`).
+    pub fn open_decompressed<P: AsRef<Path>>(
+        file_path: P,
+        memory_limit: usize,
+    ) -> Result<BinDecompressReader<BinFile>> {
+        let file = Self::open(file_path)?.buffered(DECOMPRESS_BUF_SIZE)?;
+        BinDecompressReader::new(file, memory_limit)
+    }
+
+    /// Method to open a binary file for reading through cloneable `SharedBinFile` handles
+    #[cfg(unix)]
+    pub fn open_shared<P: AsRef<Path>>(file_path: P) -> Result<SharedBinFile> {
+        let file = File::open(file_path)?;
+        SharedBinFile::new(file, 0)
+    }
+
+    /// Method to open a binary file as a read-only memory map
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap<P: AsRef<Path>>(file_path: P) -> Result<BinMmapFile> {
+        let file = File::open(file_path)?;
+        BinMmapFile::new(&file)
+    }
 }
@@ -1,90 +1,346 @@
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 
-use crate::{bin_error::Result, BinSeek, Error};
+use crate::{bin_error::Result, BinMemoryBuffer, BinSeek, CyclicRecords, Error};
 
-/// BinRingMemoryBuffer is a custom circular buffer that allows reading from a memory buffer with a position pointer.
+/// What a write to a `BinRingMemoryBuffer` does when there is not enough free space for it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The oldest data is overwritten, whether it was read or not.
+    #[default]
+    Overwrite,
+    /// A write which does not fit fails with `ErrorKind::StorageFull` and writes nothing.
+    Reject,
+    /// Only as much as fits is written, a write to a full buffer fails with
+    /// `ErrorKind::WouldBlock` until data is read.
+    Block,
+}
+
+/// BinRingMemoryBuffer is a circular buffer with separate read and write positions, usable as a FIFO.
+///
+/// Writes append after the newest data and reads consume from the oldest unread data. Bytes which
+/// were read stay in the buffer until writes need their space.
 #[derive(Default, Debug)]
 pub struct BinRingMemoryBuffer {
     buffer: Vec<u8>,
-    position: usize,
+    // The index of the oldest byte in the buffer.
+    oldest: usize,
+    // The number of bytes stored from `oldest` on, read or not.
+    stored: usize,
+    // The number of stored bytes which were read.
+    consumed: usize,
+    policy: OverflowPolicy,
 }
 
 impl BinRingMemoryBuffer {
+    /// Creates an empty buffer holding up to `capacity` bytes
+    pub fn with_capacity(capacity: usize) -> Self {
+        BinRingMemoryBuffer {
+            buffer: vec![0; capacity],
+            ..Default::default()
+        }
+    }
+
+    /// Creates a full buffer from `buffer`, reading starts at `offset`
     pub fn new_with_offset(buffer: Vec<u8>, offset: usize) -> Self {
         let mut mem_buff = BinRingMemoryBuffer::from(buffer);
-        mem_buff.position = offset;
+        if !mem_buff.buffer.is_empty() {
+            mem_buff.oldest = offset % mem_buff.buffer.len();
+        }
         mem_buff
     }
 
-    fn read_byte(&mut self) -> u8 {
-        let result = self.buffer[self.position];
-        self.next();
-        result
+    /// Sets what writes do when the buffer is full
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.policy = policy;
+        self
     }
 
-    fn read_bytes(&mut self, buffer: &mut [u8]) -> usize {
-        if self.buffer.is_empty() {
-            return 0;
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns the number of bytes the buffer can hold
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns the number of bytes which can be read
+    pub fn available(&self) -> usize {
+        self.stored - self.consumed
+    }
+
+    /// Returns the number of bytes which can be written without overwriting unread data
+    pub fn free(&self) -> usize {
+        self.capacity() - self.available()
+    }
+
+    /// Returns true if no byte can be written without overwriting unread data
+    pub fn is_full(&self) -> bool {
+        self.free() == 0
+    }
+
+    /// Returns the index in the buffer of the next byte to read
+    pub fn read_index(&self) -> usize {
+        self.wrap(self.oldest + self.consumed)
+    }
+
+    /// Returns the index in the buffer the next byte is written to
+    pub fn write_index(&self) -> usize {
+        self.wrap(self.oldest + self.stored)
+    }
+
+    /// Returns the unread data as two slices, the second one holding the part after the wrap point
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        self.slices(self.read_index(), self.available())
+    }
+
+    /// Moves the read position by `offset` bytes, wrapping around the start or the end of the
+    /// buffer. Returns the new position.
+    pub fn seek_relative(&mut self, offset: isize) -> Result<usize> {
+        let pos = self.oldest + self.consumed;
+        let to = match pos.checked_add_signed(offset) {
+            Some(to) => to,
+            None => {
+                let back = offset.unsigned_abs() - pos;
+                self.wrap(self.capacity() - self.wrap(back))
+            }
+        };
+        self.seek(to)
+    }
+
+    /// Returns the bytes from index `oldest` up to index `end` (exclusive) in chronological order,
+    /// wrapping around the end of the buffer. `end` equal to `oldest` takes the whole buffer.
+    ///
+    /// This unrolls a cyclic area such as the activity data of a tachograph card, whatever
+    /// was read or written through the buffer before.
+    pub fn linearize(&self, oldest: usize, end: usize) -> Result<BinMemoryBuffer> {
+        self.check_index(oldest)?;
+        self.check_index(end)?;
+        let len = match self.distance(oldest, end) {
+            0 => self.capacity(),
+            len => len,
+        };
+        Ok(BinMemoryBuffer::from(self.copy_range(oldest, len)))
+    }
+
+    /// Returns the records of `size` bytes from the one at index `oldest` up to and including
+    /// the one at index `newest`, iterable forwards and backwards across the wrap point.
+    pub fn fixed_records(
+        &self,
+        oldest: usize,
+        newest: usize,
+        size: usize,
+    ) -> Result<CyclicRecords<'_>> {
+        self.check_index(oldest)?;
+        self.check_index(newest)?;
+        let last = self.distance(oldest, newest);
+        if size == 0 || !last.is_multiple_of(size) {
+            return Err(Error::InvalidRecordArea(
+                "newest record is not on a record boundary",
+            ));
         }
-        for item in buffer.iter_mut() {
-            *item = self.read_byte();
+        if last + size > self.capacity() {
+            return Err(Error::InvalidRecordArea("records do not fit in the area"));
         }
-        buffer.len()
+        let records = (0..=last)
+            .step_by(size)
+            .map(|start| (start, size))
+            .collect();
+        Ok(CyclicRecords::new(self, oldest, records))
     }
 
-    fn write_byte(&mut self, byte: u8) -> std::io::Result<usize> {
+    /// Returns the variable sized records from the one at index `oldest` up to and including the
+    /// one at index `newest`, iterable forwards and backwards across the wrap point.
+    ///
+    /// Every record starts with a header of `header_len` bytes, `record_len` returns the length
+    /// of the whole record, header included, from the header bytes.
+    pub fn length_prefixed_records<F>(
+        &self,
+        oldest: usize,
+        newest: usize,
+        header_len: usize,
+        record_len: F,
+    ) -> Result<CyclicRecords<'_>>
+    where
+        F: Fn(&[u8]) -> usize,
+    {
+        self.check_index(oldest)?;
+        self.check_index(newest)?;
+        let last = self.distance(oldest, newest);
+        let mut records = Vec::new();
+        let mut start = 0;
+        loop {
+            if start + header_len > self.capacity() {
+                return Err(Error::InvalidRecordArea("records do not fit in the area"));
+            }
+            let header = self.copy_range(self.wrap(oldest + start), header_len);
+            let len = record_len(&header);
+            if len == 0 || len < header_len {
+                return Err(Error::InvalidRecordArea(
+                    "record is shorter than its header",
+                ));
+            }
+            if start + len > self.capacity() {
+                return Err(Error::InvalidRecordArea("records do not fit in the area"));
+            }
+            records.push((start, len));
+            if start == last {
+                break;
+            }
+            start += len;
+            if start > last {
+                return Err(Error::InvalidRecordArea(
+                    "newest record is not on a record boundary",
+                ));
+            }
+        }
+        Ok(CyclicRecords::new(self, oldest, records))
+    }
+
+    /// Returns `len` bytes from index `start` on, wrapping around the end of the buffer.
+    pub(crate) fn copy_range(&self, start: usize, len: usize) -> Vec<u8> {
+        let (first, second) = self.slices(start, len);
+        [first, second].concat()
+    }
+
+    fn check_index(&self, index: usize) -> Result<()> {
+        if index < self.capacity() {
+            Ok(())
+        } else {
+            Err(Error::InvalidRecordArea("pointer is outside of the area"))
+        }
+    }
+
+    /// Returns how many bytes `to` is after `from`, going forwards around the buffer.
+    fn distance(&self, from: usize, to: usize) -> usize {
+        self.wrap(to + self.capacity() - from)
+    }
+
+    fn wrap(&self, index: usize) -> usize {
         if self.buffer.is_empty() {
-            return Ok(0);
+            0
+        } else {
+            index % self.buffer.len()
         }
-        self.buffer[self.position] = byte;
-        self.next();
-        Ok(1)
+    }
+
+    /// Returns the `len` bytes from `start` on, split at the end of the buffer.
+    fn slices(&self, start: usize, len: usize) -> (&[u8], &[u8]) {
+        let first = len.min(self.buffer.len() - start);
+        (
+            &self.buffer[start..start + first],
+            &self.buffer[..len - first],
+        )
+    }
+
+    fn read_bytes(&mut self, buffer: &mut [u8]) -> usize {
+        let len = buffer.len().min(self.available());
+        let (first, second) = self.slices(self.read_index(), len);
+        buffer[..first.len()].copy_from_slice(first);
+        buffer[first.len()..len].copy_from_slice(second);
+        self.consumed += len;
+        len
     }
 
     fn write_bytes(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
         if self.buffer.is_empty() {
             return Ok(0);
         }
-        let mut ret_count: usize = 0;
-        for byte in buffer {
-            if self.position >= self.buffer.len() {
-                self.position = 0;
+        let buffer = match self.policy {
+            OverflowPolicy::Overwrite => buffer,
+            OverflowPolicy::Reject if buffer.len() > self.free() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::StorageFull,
+                    "ring buffer is full",
+                ));
             }
-            ret_count += self.write_byte(*byte)?;
-        }
-
-        Ok(ret_count)
-    }
+            OverflowPolicy::Reject => buffer,
+            OverflowPolicy::Block if self.is_full() && !buffer.is_empty() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "ring buffer is full",
+                ));
+            }
+            OverflowPolicy::Block => &buffer[..buffer.len().min(self.free())],
+        };
 
-    fn next(&mut self) {
-        let mut new_pos: usize = self.position + 1;
-        if new_pos == self.buffer.len() {
-            new_pos = 0;
+        // Only the last `capacity` bytes of a longer write stay in the buffer
+        let capacity = self.capacity();
+        let skip = buffer.len().saturating_sub(capacity);
+        let data = &buffer[skip..];
+        if skip > 0 {
+            self.oldest = self.wrap(self.oldest + self.stored + skip);
+            self.stored = 0;
+            self.consumed = 0;
+        } else {
+            let dropped = (self.stored + data.len()).saturating_sub(capacity);
+            self.oldest = self.wrap(self.oldest + dropped);
+            self.stored -= dropped;
+            self.consumed = self.consumed.saturating_sub(dropped);
         }
-        self.position = new_pos;
+
+        let start = self.write_index();
+        let first = data.len().min(capacity - start);
+        self.buffer[start..start + first].copy_from_slice(&data[..first]);
+        self.buffer[..data.len() - first].copy_from_slice(&data[first..]);
+        self.stored += data.len();
+        Ok(buffer.len())
     }
 }
 
 /// Implement the `BinSeek` trait for `BinRingMemoryBuffer` to support seeking, getting the current position, and the buffer length.
+///
+/// Positions count from index 0 of the buffer and are modular. `pos` is the index of the oldest
+/// stored byte plus the number of stored bytes read, so it is below `2 * capacity` and equals
+/// the read index modulo the capacity. `seek` takes any position modulo the capacity and moves
+/// the read position within the stored data, so bytes already read can be read again until
+/// writes overwrite them.
+///
+/// A write which overwrites the oldest data moves the start of the stored data, so `pos` may
+/// then return a value smaller by the capacity for the same unread byte. A position saved before
+/// the write still seeks to that byte, unless it was the end of a full buffer, which then maps
+/// to the oldest byte.
 impl BinSeek for BinRingMemoryBuffer {
-    fn seek(&mut self, _: usize) -> Result<usize> {
-        Err(Error::NotSupported)
+    fn seek(&mut self, to: usize) -> Result<usize> {
+        if self.buffer.is_empty() {
+            return Ok(0);
+        }
+        // Positions returned by `pos` are exact, others map to an index of the buffer
+        let consumed = match to.checked_sub(self.oldest) {
+            Some(consumed) if consumed <= self.stored => consumed,
+            _ => self.distance(self.oldest, self.wrap(to)),
+        };
+        if consumed > self.stored {
+            return Err(Error::File(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "position is outside of the stored data",
+            )));
+        }
+        self.consumed = consumed;
+        self.pos()
     }
 
     fn pos(&mut self) -> Result<usize> {
-        Ok(self.position)
+        Ok(self.oldest + self.consumed)
     }
 
     fn len(&self) -> Result<usize> {
         Ok(self.buffer.len())
     }
-    /// Return true only if buffer is empty becouse that is ring buffer.
+    /// Return true if there is no unread data, more can still be written.
     fn is_eof(&mut self) -> bool {
-        self.len().unwrap_or(0) == 0
+        self.available() == 0
     }
 }
 
 /// Implement the `Read` trait for `BinRingMemoryBuffer` to allow reading from it just like a file.
+///
+/// Only unread data is returned: `read` returns fewer bytes than asked for, or 0, when less is
+/// available, and `read_exact` fails with `ErrorKind::UnexpectedEof` without consuming anything.
 impl Read for BinRingMemoryBuffer {
     fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
         let len = self.read_bytes(buffer);
@@ -92,6 +348,9 @@ impl Read for BinRingMemoryBuffer {
     }
 
     fn read_exact(&mut self, buffer: &mut [u8]) -> std::io::Result<()> {
+        if buffer.len() > self.available() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "end of file"));
+        }
         self.read_bytes(buffer);
         Ok(())
     }
@@ -108,22 +367,20 @@ impl Write for BinRingMemoryBuffer {
     }
 }
 
-/// Allows conversion from a `Vec<u8>` to a `BinRingMemoryBuffer`
+/// Allows conversion from a `Vec<u8>` to a full `BinRingMemoryBuffer`
 impl From<Vec<u8>> for BinRingMemoryBuffer {
     fn from(buffer: Vec<u8>) -> Self {
         BinRingMemoryBuffer {
+            stored: buffer.len(),
             buffer,
-            position: 0,
+            ..Default::default()
         }
     }
 }
 
-/// Allows conversion from a byte slice (`&[u8]`) to a `BinRingMemoryBuffer`
+/// Allows conversion from a byte slice (`&[u8]`) to a full `BinRingMemoryBuffer`
 impl From<&[u8]> for BinRingMemoryBuffer {
     fn from(buffer: &[u8]) -> Self {
-        BinRingMemoryBuffer {
-            buffer: Vec::from(buffer),
-            position: 0,
-        }
+        BinRingMemoryBuffer::from(Vec::from(buffer))
     }
 }
@@ -1,4 +1,4 @@
-use crate::bin_error::Result;
+use crate::{bin_error::Result, Checkpoint};
 
 /// Define a trait for seeking in binary files
 pub trait BinSeek {
@@ -12,4 +12,15 @@ pub trait BinSeek {
     fn is_eof(&mut self) -> bool {
         self.pos().unwrap_or(0) >= self.len().unwrap_or(0)
     }
+    /// Return false if the source can only move forward (e.g. a stream).
+    fn is_seekable(&self) -> bool {
+        true
+    }
+    /// Remember the current position, the returned guard rewinds to it on drop unless committed.
+    fn checkpoint(&mut self) -> Result<Checkpoint<'_, Self>>
+    where
+        Self: Sized,
+    {
+        Checkpoint::new(self)
+    }
 }
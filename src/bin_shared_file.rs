@@ -0,0 +1,90 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    os::unix::fs::FileExt,
+    sync::Arc,
+};
+
+use crate::{
+    bin_error::{Error, Result},
+    BinSeek,
+};
+
+/// SharedBinFile is a cheap to clone handle for reading one file from several places at once,
+/// for example from multiple threads parsing different regions.
+///
+/// Every clone has its own position. Reads use positional I/O (`pread`) and never move the
+/// cursor of the underlying file. The length is taken when the handle is created, see
+/// `refresh_len`.
+#[derive(Debug, Clone)]
+pub struct SharedBinFile {
+    file: Arc<File>,
+    position: u64,
+    len: u64,
+}
+
+impl SharedBinFile {
+    pub(crate) fn new(file: File, position: u64) -> Result<Self> {
+        let len = file.metadata()?.len();
+        Ok(Self {
+            file: Arc::new(file),
+            position,
+            len,
+        })
+    }
+
+    /// Reloads the length from the file system, for a file which grows while it is read
+    pub fn refresh_len(&mut self) -> Result<usize> {
+        self.len = self.file.metadata()?.len();
+        Ok(self.len as usize)
+    }
+
+    /// Reads up to `buffer.len()` bytes at `offset`, the position is not changed
+    pub fn read_at(&self, buffer: &mut [u8], offset: u64) -> Result<usize> {
+        Ok(self.file.read_at(buffer, offset)?)
+    }
+
+    /// Reads exactly `buffer.len()` bytes at `offset`, the position is not changed
+    pub fn read_exact_at(&self, buffer: &mut [u8], offset: u64) -> Result<()> {
+        if offset
+            .checked_add(buffer.len() as u64)
+            .is_none_or(|end| end > self.len)
+        {
+            return Err(Error::File(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "end of file",
+            )));
+        }
+        self.file.read_exact_at(buffer, offset)?;
+        Ok(())
+    }
+}
+
+impl Read for SharedBinFile {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let len = self.file.read_at(buffer, self.position)?;
+        self.position += len as u64;
+        Ok(len)
+    }
+
+    fn read_exact(&mut self, buffer: &mut [u8]) -> io::Result<()> {
+        self.file.read_exact_at(buffer, self.position)?;
+        self.position += buffer.len() as u64;
+        Ok(())
+    }
+}
+
+impl BinSeek for SharedBinFile {
+    fn seek(&mut self, to: usize) -> Result<usize> {
+        self.position = to as u64;
+        Ok(to)
+    }
+
+    fn pos(&mut self) -> Result<usize> {
+        Ok(self.position as usize)
+    }
+
+    fn len(&self) -> Result<usize> {
+        Ok(self.len as usize)
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    io::{self, Read, Write},
+    path::PathBuf,
+};
+
+use crate::{bin_error::Result, BinFile, BinMemoryBuffer, BinSeek};
+
+/// Buffer size of the temp file once the data is spilled to disk.
+const FILE_BUF_SIZE: usize = 8 * 1024;
+
+#[derive(Debug)]
+enum Spooled {
+    Memory(BinMemoryBuffer),
+    File(Box<BinFile>),
+}
+
+/// BinSpooledBuffer behaves like a `BinMemoryBuffer` until its data grows past a threshold,
+/// then it moves the data to an anonymous temp file and continues there.
+///
+/// The temp file has no name in the file system and is deleted by the OS once it is closed.
+#[derive(Debug)]
+pub struct BinSpooledBuffer {
+    inner: Spooled,
+    threshold: usize,
+    dir: Option<PathBuf>,
+}
+
+impl BinSpooledBuffer {
+    /// Creates a buffer which is kept in memory up to `threshold` bytes
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            inner: Spooled::Memory(BinMemoryBuffer::new()),
+            threshold,
+            dir: None,
+        }
+    }
+
+    /// Creates a buffer which spills to a temp file in `dir` instead of the default temp directory
+    pub fn new_in<P: Into<PathBuf>>(threshold: usize, dir: P) -> Self {
+        Self {
+            dir: Some(dir.into()),
+            ..Self::new(threshold)
+        }
+    }
+
+    /// Returns true if the data has been moved to a temp file
+    pub fn is_spilled(&self) -> bool {
+        matches!(self.inner, Spooled::File(_))
+    }
+
+    /// Moves the data to a temp file now, keeping the position
+    pub fn spill(&mut self) -> Result<()> {
+        if let Spooled::Memory(memory) = &mut self.inner {
+            let file = match &self.dir {
+                Some(dir) => tempfile::tempfile_in(dir)?,
+                None => tempfile::tempfile()?,
+            };
+            let mut file = BinFile::new(file)?.buffered(FILE_BUF_SIZE)?;
+            file.write_all(memory.as_slice())?;
+            file.seek(memory.pos()?)?;
+            self.inner = Spooled::File(Box::new(file));
+        }
+        Ok(())
+    }
+
+    /// Converts into a `BinFile` over the temp file, spilling the data first if it is still in
+    /// memory. The position is kept.
+    pub fn into_bin_file(mut self) -> Result<BinFile> {
+        self.spill()?;
+        match self.inner {
+            Spooled::File(file) => Ok(*file),
+            Spooled::Memory(_) => unreachable!("the buffer was spilled"),
+        }
+    }
+}
+
+impl Read for BinSpooledBuffer {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Spooled::Memory(memory) => memory.read(buffer),
+            Spooled::File(file) => file.read(buffer),
+        }
+    }
+}
+
+impl Write for BinSpooledBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Spooled::Memory(memory) = &mut self.inner {
+            if memory.pos()? + buf.len() > self.threshold {
+                self.spill()?;
+            }
+        }
+        match &mut self.inner {
+            Spooled::Memory(memory) => memory.write(buf),
+            Spooled::File(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Spooled::Memory(memory) => memory.flush(),
+            Spooled::File(file) => file.flush(),
+        }
+    }
+}
+
+impl BinSeek for BinSpooledBuffer {
+    fn seek(&mut self, to: usize) -> Result<usize> {
+        match &mut self.inner {
+            Spooled::Memory(memory) => memory.seek(to),
+            Spooled::File(file) => file.seek(to),
+        }
+    }
+
+    fn pos(&mut self) -> Result<usize> {
+        match &mut self.inner {
+            Spooled::Memory(memory) => memory.pos(),
+            Spooled::File(file) => file.pos(),
+        }
+    }
+
+    fn len(&self) -> Result<usize> {
+        match &self.inner {
+            Spooled::Memory(memory) => memory.len(),
+            Spooled::File(file) => file.len(),
+        }
+    }
+}
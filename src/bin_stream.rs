@@ -0,0 +1,113 @@
+use std::io::{self, Read};
+
+use crate::{bin_error::Result, BinSeek, Error};
+
+/// BinStream adapts any forward-only `Read` source (stdin, a socket, a decompressor, ...)
+/// to `BinSeek`, so it can be used wherever a seekable source is expected.
+/// Seeking forward skips data, seeking backwards returns `Error::NotSupported`.
+#[derive(Debug)]
+pub struct BinStream<R: Read> {
+    reader: R,
+    position: usize,
+    // A byte read ahead by `is_eof` which has not been handed out yet.
+    peeked: Option<u8>,
+}
+
+impl<R: Read> BinStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            position: 0,
+            peeked: None,
+        }
+    }
+
+    /// Returns a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consumes the `BinStream` and returns the underlying reader.
+    /// A byte read ahead by `is_eof` is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads and discards `count` bytes, returning how many were actually skipped.
+    fn skip(&mut self, mut count: usize) -> io::Result<usize> {
+        let mut skipped = 0;
+        if count > 0 && self.peeked.take().is_some() {
+            count -= 1;
+            skipped += 1;
+        }
+        skipped += io::copy(&mut (&mut self.reader).take(count as u64), &mut io::sink())? as usize;
+        self.position += skipped;
+        Ok(skipped)
+    }
+}
+
+/// Implement the `BinSeek` trait for `BinStream`, only forward seeks are supported.
+impl<R: Read> BinSeek for BinStream<R> {
+    fn seek(&mut self, to: usize) -> Result<usize> {
+        if to < self.position {
+            return Err(Error::NotSupported);
+        }
+        let count = to - self.position;
+        if self.skip(count)? < count {
+            return Err(Error::File(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "end of file",
+            )));
+        }
+        Ok(self.position)
+    }
+
+    fn pos(&mut self) -> Result<usize> {
+        Ok(self.position)
+    }
+
+    /// The length of a stream is not known up front.
+    fn len(&self) -> Result<usize> {
+        Err(Error::NotSupported)
+    }
+
+    fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Return true if no more data can be read from the stream.
+    fn is_eof(&mut self) -> bool {
+        if self.peeked.is_some() {
+            return false;
+        }
+        let mut byte = [0u8; 1];
+        match self.reader.read_exact(&mut byte) {
+            Ok(()) => {
+                self.peeked = Some(byte[0]);
+                false
+            }
+            Err(_) => true,
+        }
+    }
+
+    fn is_seekable(&self) -> bool {
+        false
+    }
+}
+
+/// Implement the `Read` trait for `BinStream`, keeping track of the stream position.
+impl<R: Read> Read for BinStream<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        if let Some(byte) = self.peeked.take() {
+            buffer[0] = byte;
+            self.position += 1;
+            return Ok(1);
+        }
+        let len = self.reader.read(buffer)?;
+        self.position += len;
+        Ok(len)
+    }
+}
@@ -1,6 +1,6 @@
 use std::{fs::File, path::Path};
 
-use crate::{bin_error::Result, BinFile};
+use crate::{bin_error::Result, AtomicBinFile, BinFile};
 
 #[derive(Debug, Default)]
 pub struct BinWriter {}
@@ -8,9 +8,14 @@ pub struct BinWriter {}
 impl BinWriter {
     /// This function will create a file if it does not exist, and will truncate it if it does,
     /// and create a new BinFile instance from it ()
-    pub fn create(file_path: &str) -> Result<BinFile> {
-        let path = Path::new(file_path);
-        let file = File::create(path)?;
+    pub fn create<P: AsRef<Path>>(file_path: P) -> Result<BinFile> {
+        let file = File::create(file_path)?;
         BinFile::new(file)
     }
+
+    /// Creates a temporary file next to `file_path` which replaces it on `commit`,
+    /// dropping the returned handle without `commit` discards everything written
+    pub fn create_atomic<P: AsRef<Path>>(file_path: P) -> Result<AtomicBinFile> {
+        AtomicBinFile::new(file_path.as_ref())
+    }
 }
@@ -1,19 +1,60 @@
+mod bin_atomic_file;
 mod bin_bit_reader;
+mod bin_bit_stuffing;
+mod bin_bit_writer;
+mod bin_bitset;
 mod bin_byte_order;
+mod bin_chain;
+mod bin_checkpoint;
+mod bin_cyclic_records;
+mod bin_decompress;
 mod bin_error;
 mod bin_file;
+mod bin_file_buffer;
+mod bin_huffman;
+mod bin_inflate;
 mod bin_memory_buffer;
+#[cfg(feature = "mmap")]
+mod bin_mmap_file;
+mod bin_nal_unit;
+mod bin_open_options;
+mod bin_packed;
 mod bin_reader;
 mod bin_ring_memory_buffer;
 mod bin_seek;
+#[cfg(unix)]
+mod bin_shared_file;
+#[cfg(feature = "spool")]
+mod bin_spooled_buffer;
+mod bin_stream;
 mod bin_writer;
 
+pub use bin_atomic_file::AtomicBinFile;
 pub use bin_bit_reader::BitReader;
+pub use bin_bit_stuffing::{BitDestuffer, BitStuffer, StuffingRule};
+pub use bin_bit_writer::BitWriter;
+pub use bin_bitset::{BitSet, Flag, FlagSet, Ones};
 pub use bin_byte_order::*;
+pub use bin_chain::BinChain;
+pub use bin_checkpoint::{BitCheckpoint, Checkpoint};
+pub use bin_cyclic_records::CyclicRecords;
+pub use bin_decompress::{BinDecompressReader, Compression};
 pub use bin_error::{Error, Result};
-pub use bin_file::BinFile;
+pub use bin_file::{BinFile, FileLock};
+pub use bin_huffman::HuffmanTable;
+pub use bin_inflate::InflateReader;
 pub use bin_memory_buffer::*;
+#[cfg(feature = "mmap")]
+pub use bin_mmap_file::BinMmapFile;
+pub use bin_nal_unit::{AnnexBReader, NalUnit, RbspReader};
+pub use bin_open_options::BinOpenOptions;
+pub use bin_packed::BitOrder;
 pub use bin_reader::BinReader;
 pub use bin_ring_memory_buffer::*;
 pub use bin_seek::BinSeek;
+#[cfg(unix)]
+pub use bin_shared_file::SharedBinFile;
+#[cfg(feature = "spool")]
+pub use bin_spooled_buffer::BinSpooledBuffer;
+pub use bin_stream::BinStream;
 pub use bin_writer::BinWriter;
@@ -0,0 +1,86 @@
+use std::{fs, io::Write};
+
+use binary_data::{BigEndian, BinSeek, BinWriter, WriteBytes};
+use tempfile::tempdir;
+
+fn dir_entries(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_commit_replaces_target() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("firmware.bin");
+    fs::write(&path, [0xEE; 8]).unwrap();
+
+    let mut file = BinWriter::create_atomic(&path).unwrap();
+    assert_eq!(file.target_path(), path.as_path());
+    assert_eq!(file.temp_path().parent(), Some(dir.path()));
+    file.write_u16::<BigEndian>(0x0102).unwrap();
+    file.write_bytes(&[3, 4]).unwrap();
+    file.seek(0).unwrap();
+    file.write_bytes(&[9]).unwrap();
+    assert_eq!(file.len().unwrap(), 4);
+
+    // The target is untouched until the commit
+    assert_eq!(fs::read(&path).unwrap(), vec![0xEE; 8]);
+    assert_eq!(dir_entries(dir.path()).len(), 2);
+    file.commit().unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), vec![9, 2, 3, 4]);
+    assert_eq!(dir_entries(dir.path()), vec!["firmware.bin"]);
+}
+
+#[test]
+fn test_drop_without_commit_discards() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("card.ddd");
+    fs::write(&path, [1, 2, 3]).unwrap();
+
+    let mut file = BinWriter::create_atomic(&path).unwrap();
+    file.write_all(&[7; 100]).unwrap();
+    drop(file);
+    assert_eq!(fs::read(&path).unwrap(), vec![1, 2, 3]);
+    assert_eq!(dir_entries(dir.path()), vec!["card.ddd"]);
+
+    // A new file is only created by the commit
+    let new_path = dir.path().join("new.ddd");
+    let mut file = BinWriter::create_atomic(&new_path)
+        .unwrap()
+        .buffered(16)
+        .unwrap();
+    file.write_all(&[5; 40]).unwrap();
+    file.write_all(&[6; 3]).unwrap();
+    assert!(!new_path.exists());
+    file.commit().unwrap();
+    assert_eq!(fs::read(&new_path).unwrap().len(), 43);
+    assert_eq!(dir_entries(dir.path()), vec!["card.ddd", "new.ddd"]);
+}
+
+#[test]
+fn test_create_atomic_fails_for_missing_directory() {
+    let dir = tempdir().unwrap();
+    assert!(BinWriter::create_atomic(dir.path().join("missing/out.bin")).is_err());
+    assert!(BinWriter::create_atomic(dir.path().join("..")).is_err());
+}
+
+#[test]
+fn test_failed_commit_keeps_target() {
+    let dir = tempdir().unwrap();
+    // A non-empty directory can not be replaced by a file, so the rename fails
+    let path = dir.path().join("out");
+    fs::create_dir(&path).unwrap();
+    fs::write(path.join("keep.bin"), [1, 2]).unwrap();
+
+    let mut file = BinWriter::create_atomic(&path).unwrap();
+    file.write_all(&[7; 10]).unwrap();
+    assert!(file.commit().is_err());
+
+    assert_eq!(dir_entries(dir.path()), vec!["out"]);
+    assert_eq!(fs::read(path.join("keep.bin")).unwrap(), vec![1, 2]);
+}
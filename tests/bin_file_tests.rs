@@ -0,0 +1,295 @@
+use std::{
+    fs,
+    io::{ErrorKind, Write},
+};
+
+use binary_data::{BinFile, BinReader, BinSeek, BinWriter, Error};
+use tempfile::tempdir;
+
+#[test]
+fn test_patch_byte_in_existing_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("patch.ddd");
+    fs::write(&path, [1, 2, 3, 4, 5]).unwrap();
+
+    let mut file = BinFile::options()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .unwrap();
+    file.seek(2).unwrap();
+    file.write_bytes(&[0xAA]).unwrap();
+    file.seek(0).unwrap();
+    let mut data = [0u8; 5];
+    file.read_bytes(&mut data).unwrap();
+    assert_eq!(data, [1, 2, 0xAA, 4, 5]);
+    drop(file);
+
+    assert_eq!(fs::read(&path).unwrap(), vec![1, 2, 0xAA, 4, 5]);
+}
+
+#[test]
+fn test_append_and_truncate() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("log.bin");
+    fs::write(&path, [1, 2]).unwrap();
+
+    let mut file = BinFile::options().append(true).open(&path).unwrap();
+    file.write_bytes(&[3, 4]).unwrap();
+    drop(file);
+    assert_eq!(fs::read(&path).unwrap(), vec![1, 2, 3, 4]);
+
+    let mut file = BinFile::options()
+        .write(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    assert_eq!(file.len().unwrap(), 0);
+    file.write_bytes(&[9]).unwrap();
+    drop(file);
+    assert_eq!(fs::read(&path).unwrap(), vec![9]);
+}
+
+#[test]
+fn test_create_new_fails_on_existing_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("new.bin");
+
+    let mut options = BinFile::options();
+    options.write(true).create_new(true);
+    options.open(&path).unwrap().write_bytes(&[1]).unwrap();
+    match options.open(&path) {
+        Err(Error::File(err)) => assert_eq!(err.kind(), ErrorKind::AlreadyExists),
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // Without create the file must exist
+    let missing = dir.path().join("missing.bin");
+    assert!(BinFile::options().write(true).open(&missing).is_err());
+    assert!(BinFile::options()
+        .write(true)
+        .create(true)
+        .open(&missing)
+        .is_ok());
+}
+
+#[test]
+fn test_reader_and_writer_take_paths() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("data.bin");
+
+    let mut file = BinWriter::create(&path).unwrap();
+    file.write_bytes(&[7, 8]).unwrap();
+    drop(file);
+
+    let mut file = BinReader::open(path.as_path()).unwrap();
+    assert_eq!(file.read_n_bytes::<2>().unwrap(), [7, 8]);
+    assert!(file.write_bytes(&[1]).is_err());
+
+    match BinReader::open(dir.path().join("missing.bin")) {
+        Err(Error::File(err)) => assert_eq!(err.kind(), ErrorKind::NotFound),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_length_follows_writes() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("rmw.bin");
+    fs::write(&path, [1, 2, 3]).unwrap();
+
+    let mut file = BinFile::options()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .unwrap();
+    assert_eq!(file.read_n_bytes::<3>().unwrap(), [1, 2, 3]);
+    assert!(file.is_eof().unwrap());
+
+    // Overwrite the last byte and extend the file
+    file.seek(2).unwrap();
+    file.write_bytes(&[0x33, 4, 5]).unwrap();
+    assert_eq!(file.len().unwrap(), 5);
+    assert!(file.is_eof().unwrap());
+
+    file.seek(1).unwrap();
+    assert!(!file.is_eof().unwrap());
+    file.skip_n_bytes::<1>().unwrap();
+    let mut data = [0u8; 3];
+    file.read_bytes(&mut data).unwrap();
+    assert_eq!(data, [0x33, 4, 5]);
+
+    // Writing inside the file does not change its length
+    file.seek(0).unwrap();
+    file.write_bytes(&[0x11]).unwrap();
+    assert_eq!(file.len().unwrap(), 5);
+    assert!(file.read_n_bytes::<5>().is_err());
+}
+
+#[test]
+fn test_set_len_and_truncate() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("len.bin");
+    let mut file = BinFile::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(&path)
+        .unwrap();
+    file.write_bytes(&[1, 2, 3, 4, 5, 6]).unwrap();
+
+    file.set_len(8).unwrap();
+    assert_eq!(file.len().unwrap(), 8);
+    assert_eq!(file.pos().unwrap(), 6);
+    assert_eq!(file.read_n_bytes::<2>().unwrap(), [0, 0]);
+
+    file.seek(4).unwrap();
+    file.truncate().unwrap();
+    assert_eq!(file.len().unwrap(), 4);
+    assert!(file.is_eof().unwrap());
+    assert!(file.read_n_bytes::<1>().is_err());
+
+    file.sync_all().unwrap();
+    file.sync_data().unwrap();
+    assert_eq!(fs::read(&path).unwrap(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn test_refresh_metadata_after_external_change() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("shared.bin");
+    fs::write(&path, [1, 2]).unwrap();
+
+    let mut file = BinReader::open(&path).unwrap();
+    fs::write(&path, [1, 2, 3, 4]).unwrap();
+    assert_eq!(file.len().unwrap(), 2);
+
+    assert_eq!(file.refresh_metadata().unwrap().len(), 4);
+    assert_eq!(file.len().unwrap(), 4);
+    assert_eq!(file.read_n_bytes::<4>().unwrap(), [1, 2, 3, 4]);
+}
+
+#[test]
+fn test_buffered_matches_unbuffered_model() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("buffered.bin");
+    let initial: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+    fs::write(&path, &initial).unwrap();
+
+    let mut model = initial;
+    let mut file = BinFile::options()
+        .read(true)
+        .write(true)
+        .buffered(64)
+        .open(&path)
+        .unwrap();
+    assert!(file.is_buffered());
+
+    // A fixed pseudo random mix of seeks, reads and writes of various sizes
+    let mut state = 12345u32;
+    let mut next = |max: u32| {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (state >> 8) % max
+    };
+    let mut pos = 0usize;
+    for step in 0..2000u32 {
+        match next(4) {
+            0 => {
+                pos = next(model.len() as u32 + 10) as usize;
+                file.seek(pos).unwrap();
+            }
+            1 => {
+                let len = next(100) as usize;
+                let mut data = vec![0u8; len];
+                let expected_ok = pos + len <= model.len();
+                assert_eq!(file.read_bytes(&mut data).is_ok(), expected_ok, "{}", step);
+                if expected_ok {
+                    assert_eq!(data, model[pos..pos + len], "{}", step);
+                    pos += len;
+                } else {
+                    file.seek(pos).unwrap();
+                }
+            }
+            _ => {
+                let len = next(90) as usize;
+                let data: Vec<u8> = (0..len).map(|i| (step as usize + i) as u8).collect();
+                file.write_bytes(&data).unwrap();
+                if pos + len > model.len() {
+                    model.resize(pos + len, 0);
+                }
+                model[pos..pos + len].copy_from_slice(&data);
+                pos += len;
+            }
+        }
+        assert_eq!(file.pos().unwrap(), pos, "{}", step);
+        assert_eq!(file.len().unwrap(), model.len(), "{}", step);
+    }
+    drop(file);
+    assert_eq!(fs::read(&path).unwrap(), model);
+}
+
+#[test]
+fn test_buffered_flush_and_append() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("append.bin");
+    fs::write(&path, [1, 2]).unwrap();
+
+    let mut file = BinFile::options()
+        .read(true)
+        .append(true)
+        .buffered(16)
+        .open(&path)
+        .unwrap();
+    file.write_bytes(&[3, 4]).unwrap();
+    // Nothing is written until the buffer is flushed
+    assert_eq!(fs::read(&path).unwrap(), vec![1, 2]);
+    file.flush().unwrap();
+    assert_eq!(fs::read(&path).unwrap(), vec![1, 2, 3, 4]);
+
+    file.seek(0).unwrap();
+    assert_eq!(file.read_n_bytes::<2>().unwrap(), [1, 2]);
+    file.write_bytes(&[5]).unwrap();
+    assert_eq!(file.pos().unwrap(), 5);
+    assert!(file.is_eof().unwrap());
+
+    // Switching buffering off writes the rest and keeps the position
+    let mut file = file.buffered(0).unwrap();
+    assert!(!file.is_buffered());
+    assert_eq!(file.pos().unwrap(), 5);
+    assert_eq!(fs::read(&path).unwrap(), vec![1, 2, 3, 4, 5]);
+
+    let mut file = file.buffered(8).unwrap();
+    file.write_bytes(&[6]).unwrap();
+    file.sync_all().unwrap();
+    assert_eq!(fs::read(&path).unwrap(), vec![1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn test_buffered_set_len_and_refresh() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("set_len.bin");
+    fs::write(&path, [1, 2, 3, 4, 5, 6]).unwrap();
+
+    let mut file = BinReader::open(&path).unwrap().buffered(4).unwrap();
+    assert_eq!(file.read_n_bytes::<2>().unwrap(), [1, 2]);
+    fs::write(&path, [9, 9, 9, 9, 9, 9, 9]).unwrap();
+    // The buffered window still holds the old bytes
+    assert_eq!(file.read_n_bytes::<1>().unwrap(), [3]);
+    file.refresh_metadata().unwrap();
+    assert_eq!(file.len().unwrap(), 7);
+    assert_eq!(file.read_n_bytes::<4>().unwrap(), [9, 9, 9, 9]);
+    drop(file);
+
+    let mut file = BinFile::options()
+        .read(true)
+        .write(true)
+        .buffered(4)
+        .open(&path)
+        .unwrap();
+    file.seek(5).unwrap();
+    file.write_bytes(&[7, 8, 9]).unwrap();
+    file.seek(3).unwrap();
+    file.truncate().unwrap();
+    assert_eq!(file.len().unwrap(), 3);
+    assert_eq!(fs::read(&path).unwrap(), vec![9, 9, 9]);
+}
@@ -1,4 +1,8 @@
-use binary_data::{BinMemoryBuffer, BitReader};
+use binary_data::{
+    BigEndian, BinMemoryBuffer, BinRingMemoryBuffer, BinSeek, BinStream, BitReader, Error,
+    LittleEndian, ReadBytes,
+};
+use std::io::Read;
 
 #[test]
 fn test_bit_cursor() {
@@ -40,6 +44,26 @@ fn test_read_bits_aligned() {
     assert_eq!(bit_reader.bit_cursor(), 32);
 }
 
+#[test]
+fn test_read_64_bits_at_eof() {
+    let data: Vec<u8> = (1..=9).collect();
+    for capacity in [1, 3, 64] {
+        let mut bit_reader =
+            BitReader::with_capacity(BinMemoryBuffer::from(data.clone()), capacity);
+        assert_eq!(bit_reader.read_bits(12).unwrap(), 0x010);
+        // Only 60 bits are left, a failed read does not move the position
+        assert!(bit_reader.read_bits(64).is_err());
+        assert_eq!(bit_reader.bit_cursor(), 12);
+        assert!(bit_reader.read_bits(61).is_err());
+        assert_eq!(bit_reader.read_bits(60).unwrap(), 0x0203_0405_0607_0809);
+    }
+
+    let mut bit_reader = BitReader::forward_only(std::io::Cursor::new(data));
+    assert_eq!(bit_reader.read_bits(64).unwrap(), 0x0102_0304_0506_0708);
+    assert!(bit_reader.read_bits(57).is_err());
+    assert_eq!(bit_reader.read_bits(8).unwrap(), 0x09);
+}
+
 #[test]
 fn test_fill_buf_sync() {
     let data = vec![0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
@@ -58,3 +82,404 @@ fn test_fill_buf_sync() {
     let val = bit_reader.read_bits(8).unwrap();
     assert_eq!(val, 0x33);
 }
+
+/// Reference bit extraction used to validate the cached reader.
+fn reference_bits(data: &[u8], bit_pos: usize, num_bits: usize) -> u64 {
+    (bit_pos..bit_pos + num_bits).fold(0u64, |acc, pos| {
+        (acc << 1) | ((data[pos >> 3] >> (7 - (pos & 7))) & 1) as u64
+    })
+}
+
+#[test]
+fn test_read_bits_unaligned_across_refills() {
+    let data: Vec<u8> = (0..97u32).map(|i| (i * 151 + 7) as u8).collect();
+    // Capacity which is not a multiple of 8 mixes the fast and the slow refill paths
+    let mut bit_reader = BitReader::with_capacity(BinMemoryBuffer::from(data.clone()), 13);
+
+    let widths = [1u8, 7, 13, 64, 3, 57, 33, 8, 17, 56, 2, 63, 5];
+    let mut bit_pos = 0;
+    for &width in widths.iter().cycle() {
+        if bit_pos + width as usize > data.len() * 8 {
+            break;
+        }
+        let val = bit_reader.read_bits(width).unwrap();
+        assert_eq!(
+            val,
+            reference_bits(&data, bit_pos, width as usize),
+            "bit {}",
+            bit_pos
+        );
+        bit_pos += width as usize;
+        assert_eq!(bit_reader.bit_cursor(), bit_pos as u64);
+    }
+}
+
+#[test]
+fn test_seek_bits_inside_and_outside_buffer() {
+    let data: Vec<u8> = (0..64u32).map(|i| (i * 37 + 11) as u8).collect();
+    let mut bit_reader = BitReader::with_capacity(BinMemoryBuffer::from(data.clone()), 16);
+
+    bit_reader.read_bits(40).unwrap();
+    // Backwards inside the buffer
+    bit_reader.seek_bits(3).unwrap();
+    assert_eq!(bit_reader.bit_cursor(), 3);
+    assert_eq!(
+        bit_reader.read_bits(20).unwrap(),
+        reference_bits(&data, 3, 20)
+    );
+
+    // Forwards outside the buffer
+    bit_reader.seek_bits(301).unwrap();
+    assert_eq!(bit_reader.bit_cursor(), 301);
+    assert_eq!(
+        bit_reader.read_bits(45).unwrap(),
+        reference_bits(&data, 301, 45)
+    );
+
+    // Backwards outside the buffer
+    bit_reader.seek_bits(9).unwrap();
+    assert_eq!(
+        bit_reader.read_bits(31).unwrap(),
+        reference_bits(&data, 9, 31)
+    );
+}
+
+/// A `BinMemoryBuffer` which counts how often it is seeked.
+struct CountingSeeks {
+    inner: BinMemoryBuffer,
+    seeks: usize,
+}
+
+impl Read for CountingSeeks {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buffer)
+    }
+}
+
+impl BinSeek for CountingSeeks {
+    fn seek(&mut self, to: usize) -> binary_data::Result<usize> {
+        self.seeks += 1;
+        self.inner.seek(to)
+    }
+
+    fn pos(&mut self) -> binary_data::Result<usize> {
+        self.inner.pos()
+    }
+
+    fn len(&self) -> binary_data::Result<usize> {
+        self.inner.len()
+    }
+}
+
+#[test]
+fn test_seek_bits_inside_buffer_keeps_reader() {
+    let data: Vec<u8> = (0..64u32).map(|i| (i * 37 + 11) as u8).collect();
+    let source = CountingSeeks {
+        inner: BinMemoryBuffer::from(data.clone()),
+        seeks: 0,
+    };
+    let mut bit_reader = BitReader::with_capacity(source, 4);
+
+    // The cache holds bits from bytes before the current buffer
+    assert_eq!(
+        bit_reader.read_bits(12).unwrap(),
+        reference_bits(&data, 0, 12)
+    );
+    bit_reader.seek_bits(12).unwrap();
+    bit_reader.skip_bits(4).unwrap();
+    bit_reader.seek_bits(9).unwrap();
+    assert_eq!(
+        bit_reader.read_bits(20).unwrap(),
+        reference_bits(&data, 9, 20)
+    );
+    bit_reader.seek_bits(20).unwrap();
+    assert_eq!(
+        bit_reader.read_bits(7).unwrap(),
+        reference_bits(&data, 20, 7)
+    );
+    assert_eq!(bit_reader.into_inner().seeks, 0);
+}
+
+#[test]
+fn test_read_bits_eof_does_not_consume() {
+    let data = vec![0xAB, 0xCD];
+    let mut bit_reader = BitReader::new(BinMemoryBuffer::from(data));
+
+    assert_eq!(bit_reader.read_bits(4).unwrap(), 0xA);
+    assert!(bit_reader.read_bits(13).is_err());
+    assert_eq!(bit_reader.bit_cursor(), 4);
+    assert_eq!(bit_reader.read_bits(12).unwrap(), 0xBCD);
+    assert!(bit_reader.read_bit().is_err());
+}
+
+#[test]
+fn test_forward_only_reader() {
+    let data: Vec<u8> = (0..40u32).map(|i| (i * 73 + 5) as u8).collect();
+    let mut bit_reader = BitReader::with_capacity(BinStream::new(data.as_slice()), 8);
+
+    assert_eq!(
+        bit_reader.read_bits(12).unwrap(),
+        reference_bits(&data, 0, 12)
+    );
+    // Backwards inside the buffer is still possible
+    bit_reader.seek_bits(2).unwrap();
+    assert_eq!(
+        bit_reader.read_bits(9).unwrap(),
+        reference_bits(&data, 2, 9)
+    );
+    // Forward past the buffer skips the data in between
+    bit_reader.seek_bits(133).unwrap();
+    assert_eq!(bit_reader.bit_cursor(), 133);
+    assert_eq!(
+        bit_reader.read_bits(20).unwrap(),
+        reference_bits(&data, 133, 20)
+    );
+    bit_reader.skip_bits(30).unwrap();
+    assert_eq!(
+        bit_reader.read_bits(7).unwrap(),
+        reference_bits(&data, 183, 7)
+    );
+
+    // Backwards past the buffer and peeking outside of it are rejected
+    let cursor = bit_reader.bit_cursor();
+    assert!(matches!(bit_reader.seek_bits(0), Err(Error::NotSupported)));
+    assert!(matches!(
+        bit_reader.read_bit_at(1),
+        Err(Error::NotSupported)
+    ));
+    assert!(matches!(
+        bit_reader.read_bit_at(300),
+        Err(Error::NotSupported)
+    ));
+    assert_eq!(bit_reader.bit_cursor(), cursor);
+    assert_eq!(
+        bit_reader.read_bits(10).unwrap(),
+        reference_bits(&data, 190, 10)
+    );
+
+    // Seeking past the end leaves the cursor at the end of the stream
+    assert!(bit_reader.seek_bits(1000).is_err());
+    assert_eq!(bit_reader.bit_cursor(), 320);
+}
+
+#[test]
+fn test_forward_only_from_read() {
+    let data = vec![0b1010_0000u8, 0xFF];
+    let mut bit_reader = BitReader::forward_only(std::io::Cursor::new(data));
+    assert_eq!(bit_reader.read_bits(3).unwrap(), 0b101);
+    assert_eq!(bit_reader.read_bits(13).unwrap(), 0xFF);
+    assert!(bit_reader.read_bit().is_err());
+}
+
+/// A `Read` source which hands out at most 3 bytes per call.
+struct ShortReads<'a>(&'a [u8]);
+
+impl Read for ShortReads<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let len = buffer.len().min(self.0.len()).min(3);
+        buffer[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn test_forward_only_seek_inside_cache() {
+    let data: Vec<u8> = (0..40u32).map(|i| (i * 73 + 5) as u8).collect();
+    let mut bit_reader = BitReader::with_capacity(BinStream::new(data.as_slice()), 4);
+    assert_eq!(
+        bit_reader.read_bits(12).unwrap(),
+        reference_bits(&data, 0, 12)
+    );
+    // The cache holds bits from bytes before the current buffer
+    bit_reader.seek_bits(12).unwrap();
+    bit_reader.skip_bits(4).unwrap();
+    assert_eq!(
+        bit_reader.read_bits(9).unwrap(),
+        reference_bits(&data, 16, 9)
+    );
+    bit_reader.seek_bits(10).unwrap();
+    assert_eq!(
+        bit_reader.read_bits(30).unwrap(),
+        reference_bits(&data, 10, 30)
+    );
+
+    let mut bit_reader = BitReader::forward_only(ShortReads(&data));
+    assert_eq!(
+        bit_reader.read_bits(5).unwrap(),
+        reference_bits(&data, 0, 5)
+    );
+    bit_reader.skip_bits(3).unwrap();
+    let mut bit_pos = 8;
+    for width in [7u8, 31, 1, 56, 13, 64, 9] {
+        assert_eq!(
+            bit_reader.read_bits(width).unwrap(),
+            reference_bits(&data, bit_pos, width as usize)
+        );
+        // Step back into the bits just read and skip over them again
+        bit_reader.seek_bits(bit_pos + width as usize / 2).unwrap();
+        bit_reader
+            .skip_bits(width as usize - width as usize / 2)
+            .unwrap();
+        bit_pos += width as usize;
+        assert_eq!(bit_reader.bit_cursor(), bit_pos as u64);
+    }
+}
+
+#[test]
+fn test_forward_only_seek_past_end() {
+    let data: Vec<u8> = (0..40u32).map(|i| i as u8).collect();
+    // Whether the stream is drained already or still has to be skipped, a failed seek
+    // leaves the cursor at the end of the stream
+    for capacity in [4, 64] {
+        let mut bit_reader = BitReader::with_capacity(BinStream::new(data.as_slice()), capacity);
+        assert_eq!(bit_reader.read_bits(8).unwrap(), 0);
+        assert!(bit_reader.seek_bits(1000).is_err());
+        assert_eq!(bit_reader.bit_cursor(), 320);
+        assert!(bit_reader.read_bit().is_err());
+    }
+}
+
+#[test]
+fn test_bin_stream_seek_and_eof() {
+    let data = [1u8, 2, 3, 4];
+    let mut stream = BinStream::new(&data[..]);
+    assert!(!stream.is_eof());
+    assert_eq!(stream.seek(2).unwrap(), 2);
+    assert!(matches!(stream.seek(1), Err(Error::NotSupported)));
+    let mut buf = [0u8; 2];
+    std::io::Read::read_exact(&mut stream, &mut buf).unwrap();
+    assert_eq!(buf, [3, 4]);
+    assert!(stream.is_eof());
+    assert_eq!(stream.pos().unwrap(), 4);
+}
+
+#[test]
+fn test_bit_reader_over_ring_buffer() {
+    let ring = BinRingMemoryBuffer::new_with_offset(vec![0xF0u8, 0x0F], 1);
+    let mut bit_reader = BitReader::with_capacity(ring, 1);
+    // Reading starts at the offset and wraps around, but stops after the stored data
+    assert_eq!(bit_reader.read_bits(16).unwrap(), 0x0FF0);
+    assert!(bit_reader.read_bits(8).is_err());
+    // Positions are modular, byte 0 of the buffer is the newest byte
+    bit_reader.seek_bits(4).unwrap();
+    assert_eq!(bit_reader.read_bits(4).unwrap(), 0x0);
+    bit_reader.seek_bits(8 * 4).unwrap();
+    assert_eq!(bit_reader.read_bits(8).unwrap(), 0xF0);
+}
+
+#[test]
+fn test_read_bytes_unaligned() {
+    let data = vec![0b1011_0001, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD];
+    let mut bit_reader = BitReader::new(BinMemoryBuffer::from(data));
+
+    // A 4 bit header followed by byte-order aware fields
+    assert_eq!(bit_reader.read_bits(4).unwrap(), 0b1011);
+    assert!(!bit_reader.is_byte_aligned());
+    assert_eq!(bit_reader.read_u16::<BigEndian>().unwrap(), 0x1234);
+    assert_eq!(bit_reader.read_u16::<LittleEndian>().unwrap(), 0x7856);
+    assert_eq!(bit_reader.read_u8().unwrap(), 0x9A);
+    assert_eq!(bit_reader.bit_cursor(), 44);
+    // Only 12 bits are left, so a 2 byte read can not be satisfied
+    assert!(bit_reader.read_u16::<BigEndian>().is_err());
+}
+
+#[test]
+fn test_read_exact_aligned_and_unaligned() {
+    let data: Vec<u8> = (0..200u32).map(|i| (i * 29 + 3) as u8).collect();
+    let mut bit_reader = BitReader::with_capacity(BinMemoryBuffer::from(data.clone()), 16);
+
+    let mut buf = [0u8; 50];
+    bit_reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf[..], &data[..50]);
+
+    bit_reader.read_bits(3).unwrap();
+    bit_reader.read_exact(&mut buf).unwrap();
+    for (i, byte) in buf.iter().enumerate() {
+        assert_eq!(*byte as u64, reference_bits(&data, 403 + i * 8, 8));
+    }
+
+    bit_reader.byte_align();
+    assert!(bit_reader.is_byte_aligned());
+    assert_eq!(bit_reader.bit_cursor(), 808);
+    // A read larger than the internal buffer
+    let mut rest = Vec::new();
+    bit_reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(&rest[..], &data[101..]);
+    assert_eq!(bit_reader.bit_cursor(), 1600);
+}
+
+/// Builds a capture with the 32 bit sync word `sync` inserted at each of `positions`.
+fn capture_with_sync(len: usize, sync: u32, positions: &[usize]) -> Vec<u8> {
+    let mut data: Vec<u8> = (0..len as u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8 & 0x55)
+        .collect();
+    for &pos in positions {
+        for bit in 0..32 {
+            let value = (sync >> (31 - bit)) & 1;
+            let idx = pos + bit;
+            data[idx >> 3] =
+                (data[idx >> 3] & !(0x80 >> (idx & 7))) | ((value as u8) << (7 - (idx & 7)));
+        }
+    }
+    data
+}
+
+#[test]
+fn test_find_pattern() {
+    const ASM: u32 = 0x1ACF_FC1D;
+    let data = capture_with_sync(4096, ASM, &[1237, 20011]);
+    let mut bit_reader = BitReader::with_capacity(BinMemoryBuffer::from(data), 100);
+
+    assert_eq!(
+        bit_reader.find_pattern(ASM as u64, 32, u64::MAX).unwrap(),
+        Some(1237)
+    );
+    assert_eq!(bit_reader.bit_cursor(), 1237);
+    assert_eq!(bit_reader.read_bits(32).unwrap(), ASM as u64);
+
+    // The search window ends before the second sync word
+    assert_eq!(bit_reader.find_pattern(ASM as u64, 32, 1000).unwrap(), None);
+    assert_eq!(bit_reader.bit_cursor(), 2269);
+    assert_eq!(
+        bit_reader.find_pattern(ASM as u64, 32, u64::MAX).unwrap(),
+        Some(20011)
+    );
+
+    bit_reader.skip_bits(1).unwrap();
+    assert_eq!(
+        bit_reader.find_pattern(ASM as u64, 32, u64::MAX).unwrap(),
+        None
+    );
+    assert!(matches!(
+        bit_reader.find_pattern(1, 0, 10),
+        Err(Error::NotSupported)
+    ));
+}
+
+#[test]
+fn test_find_pattern_with_tolerance() {
+    const ASM: u32 = 0x1ACF_FC1D;
+    // Two bit errors in the sync word
+    let data = capture_with_sync(512, ASM ^ 0x0100_0200, &[301]);
+    let mut bit_reader = BitReader::new(BinMemoryBuffer::from(data));
+
+    assert_eq!(
+        bit_reader.find_pattern(ASM as u64, 32, u64::MAX).unwrap(),
+        None
+    );
+    bit_reader.seek_bits(0).unwrap();
+    assert_eq!(
+        bit_reader
+            .find_pattern_with_tolerance(ASM as u64, 32, u64::MAX, 1)
+            .unwrap(),
+        None
+    );
+    bit_reader.seek_bits(0).unwrap();
+    assert_eq!(
+        bit_reader
+            .find_pattern_with_tolerance(ASM as u64, 32, u64::MAX, 2)
+            .unwrap(),
+        Some(301)
+    );
+}
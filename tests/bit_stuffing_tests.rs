@@ -0,0 +1,110 @@
+use std::io::Read;
+
+use binary_data::{
+    BinMemoryBuffer, BitDestuffer, BitReader, BitStuffer, BitWriter, Error, StuffingRule,
+};
+
+#[test]
+fn test_bit_writer() {
+    let mut writer = BitWriter::new(Vec::new());
+    writer.write_bits(0b101, 3).unwrap();
+    writer.write_bit(true).unwrap();
+    writer.write_bits(0xABCD, 16).unwrap();
+    writer.write_bits(0x0123_4567_89AB_CDEF, 64).unwrap();
+    assert_eq!(writer.bit_count(), 84);
+    assert!(!writer.is_byte_aligned());
+    let out = writer.finish().unwrap();
+    assert_eq!(
+        out,
+        vec![0xBA, 0xBC, 0xD0, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0]
+    );
+}
+
+#[test]
+fn test_hdlc_stuffing() {
+    // 0x7E as payload needs a stuff bit: 0111110 1 0
+    let mut stuffer = BitStuffer::new(Vec::new(), StuffingRule::Hdlc);
+    stuffer.write_raw_bits(0x7E, 8).unwrap();
+    stuffer.write_bits(0x7E, 8).unwrap();
+    stuffer.write_bits(0xFF, 8).unwrap();
+    assert_eq!(stuffer.raw_bit_offset(), 8 + 9 + 9);
+    let raw = stuffer.finish().unwrap();
+    // 01111110 | 0111110 1 0 | 11111 0 111 | pad
+    assert_eq!(raw, vec![0x7E, 0x7D, 0x7D, 0xC0]);
+
+    let mut bit_reader = BitReader::new(BinMemoryBuffer::from(raw));
+    assert_eq!(bit_reader.read_bits(8).unwrap(), 0x7E);
+    let mut destuffer = BitDestuffer::new(bit_reader, StuffingRule::Hdlc);
+    assert_eq!(destuffer.read_bits(8).unwrap(), 0x7E);
+    assert_eq!(destuffer.read_bits(8).unwrap(), 0xFF);
+    assert_eq!(destuffer.raw_bit_offset(), 26);
+}
+
+#[test]
+fn test_hdlc_violation() {
+    // Six ones in the payload: a flag or abort where data was expected
+    let raw = vec![0b0011_1111, 0b1000_0000];
+    let mut destuffer = BitDestuffer::new(
+        BitReader::new(BinMemoryBuffer::from(raw)),
+        StuffingRule::Hdlc,
+    );
+    assert_eq!(destuffer.read_bits(7).unwrap(), 0b0011111);
+    match destuffer.read_bit() {
+        Err(Error::StuffingViolation(offset)) => assert_eq!(offset, 7),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn test_can_stuffing_round_trip() {
+    // Runs of zeros and ones, including a run which continues into the stuff bit
+    let bits: Vec<bool> = [0u8, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 0]
+        .iter()
+        .map(|&b| b == 1)
+        .collect();
+    let mut stuffer = BitStuffer::new(Vec::new(), StuffingRule::Can);
+    for &bit in &bits {
+        stuffer.write_bit(bit).unwrap();
+    }
+    // 00000 [1] 11111 [0] 11 0000 1 0 -> the stuffed 1 starts the run of ones
+    assert_eq!(stuffer.raw_bit_offset(), 20);
+    let raw = stuffer.finish().unwrap();
+
+    let mut destuffer = BitDestuffer::new(
+        BitReader::new(BinMemoryBuffer::from(raw)),
+        StuffingRule::Can,
+    );
+    for (idx, &bit) in bits.iter().enumerate() {
+        assert_eq!(destuffer.read_bit().unwrap(), bit, "bit {}", idx);
+    }
+}
+
+#[test]
+fn test_can_violation_and_read() {
+    // Six zeros in a row
+    let raw = vec![0b1000_0001, 0xFF];
+    let mut destuffer = BitDestuffer::new(
+        BitReader::new(BinMemoryBuffer::from(raw)),
+        StuffingRule::Can,
+    );
+    let mut byte = [0u8; 1];
+    match destuffer.read(&mut byte) {
+        Err(err) => assert!(matches!(
+            err.get_ref().and_then(|e| e.downcast_ref::<Error>()),
+            Some(Error::StuffingViolation(6))
+        )),
+        Ok(len) => panic!("read {} bytes", len),
+    }
+
+    // Destuffed bytes can be read through another BitReader
+    let mut stuffer = BitStuffer::new(Vec::new(), StuffingRule::Can);
+    stuffer.write_bits(0x0000_00FF_FF0F, 40).unwrap();
+    let raw = stuffer.finish().unwrap();
+    let destuffer = BitDestuffer::new(
+        BitReader::new(BinMemoryBuffer::from(raw)),
+        StuffingRule::Can,
+    );
+    let mut bit_reader = BitReader::forward_only(destuffer);
+    assert_eq!(bit_reader.read_bits(20).unwrap(), 0x0000F);
+    assert_eq!(bit_reader.read_bits(20).unwrap(), 0xFFF0F);
+}
@@ -0,0 +1,114 @@
+use binary_data::{BinMemoryBuffer, BitReader, BitSet, BitWriter, Flag, FlagSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlotStatus {
+    DriverCardInserted,
+    CoDriverCardInserted,
+    Overspeed,
+}
+
+impl Flag for SlotStatus {
+    fn from_bit(bit: usize) -> Option<Self> {
+        match bit {
+            0 => Some(SlotStatus::DriverCardInserted),
+            1 => Some(SlotStatus::CoDriverCardInserted),
+            6 => Some(SlotStatus::Overspeed),
+            _ => None,
+        }
+    }
+
+    fn bit(self) -> usize {
+        match self {
+            SlotStatus::DriverCardInserted => 0,
+            SlotStatus::CoDriverCardInserted => 1,
+            SlotStatus::Overspeed => 6,
+        }
+    }
+}
+
+#[test]
+fn test_read_bitset_matches_read_bits() {
+    let data = vec![0b1010_0001, 0b0000_0011, 0xF0];
+    let mut reader = BitReader::new(BinMemoryBuffer::from(data.clone()));
+    reader.read_bits(3).unwrap();
+    let set = reader.read_bitset(13).unwrap();
+
+    let mut reference = BitReader::new(BinMemoryBuffer::from(data));
+    reference.read_bits(3).unwrap();
+    let value = reference.read_bits(13).unwrap();
+
+    assert_eq!(set.len(), 13);
+    assert_eq!(set.to_u64(), Some(value));
+    assert_eq!(set, BitSet::from_u64(value, 13));
+    let ones: Vec<usize> = set.iter_ones().collect();
+    let expected: Vec<usize> = (0..13).filter(|i| value >> i & 1 == 1).collect();
+    assert_eq!(ones, expected);
+    assert_eq!(set.count_ones(), expected.len());
+    assert_eq!(reader.bit_cursor(), 16);
+}
+
+#[test]
+fn test_wide_bitset_round_trip() {
+    for len in [0usize, 1, 63, 64, 65, 128, 129, 200] {
+        let mut set = BitSet::new(len);
+        assert!(set.none());
+        for bit in (0..len).filter(|b| b % 7 == 0 || b % 64 == 63) {
+            set.insert(bit);
+        }
+        if len > 10 {
+            set.remove(7);
+            assert!(!set.contains(7));
+        }
+        assert!(!set.contains(len));
+
+        let mut writer = BitWriter::new(Vec::new());
+        writer.write_bits(0b1, 1).unwrap();
+        writer.write_bitset(&set).unwrap();
+        writer.write_bits(0b01, 2).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let mut reader = BitReader::new(BinMemoryBuffer::from(bytes));
+        assert!(reader.read_bit().unwrap());
+        let read = reader.read_bitset(len).unwrap();
+        assert_eq!(read, set, "{} bits", len);
+        assert_eq!(
+            read.iter_ones().collect::<Vec<_>>(),
+            (0..len).filter(|&b| set.contains(b)).collect::<Vec<_>>()
+        );
+        assert_eq!(reader.read_bits(2).unwrap(), 0b01);
+    }
+}
+
+#[test]
+fn test_flags_keep_unknown_bits() {
+    // Overspeed, bit 4 (unknown) and the driver card
+    let mut reader = BitReader::new(BinMemoryBuffer::from(vec![0b0101_0001]));
+    let mut flags: FlagSet<SlotStatus> = reader.read_flags(8).unwrap();
+
+    assert!(flags.contains(SlotStatus::DriverCardInserted));
+    assert!(!flags.contains(SlotStatus::CoDriverCardInserted));
+    assert_eq!(
+        flags.iter().collect::<Vec<_>>(),
+        vec![SlotStatus::DriverCardInserted, SlotStatus::Overspeed]
+    );
+    assert!(flags.has_unknown());
+    assert_eq!(flags.unknown_bits().collect::<Vec<_>>(), vec![4]);
+
+    flags.remove(SlotStatus::DriverCardInserted);
+    flags.insert(SlotStatus::CoDriverCardInserted);
+    let mut writer = BitWriter::new(Vec::new());
+    writer.write_bitset(flags.bits()).unwrap();
+    assert_eq!(writer.finish().unwrap(), vec![0b0101_0010]);
+}
+
+#[test]
+fn test_read_bitset_past_end() {
+    let mut reader = BitReader::new(BinMemoryBuffer::from(vec![0xFF, 0xFF]));
+    assert!(reader.read_bitset(17).is_err());
+}
+
+#[test]
+#[should_panic(expected = "a u64 holds at most 64 bits")]
+fn test_from_u64_too_long() {
+    BitSet::from_u64(1, 65);
+}
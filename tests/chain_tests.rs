@@ -0,0 +1,81 @@
+use std::{fs, io::Read};
+
+use binary_data::{
+    BigEndian, BinChain, BinMemoryBuffer, BinReader, BinSeek, BitReader, Error, ReadBytes,
+};
+use tempfile::tempdir;
+
+fn chain(parts: &[&[u8]]) -> BinChain<BinMemoryBuffer> {
+    BinChain::from_sources(parts.iter().map(|part| BinMemoryBuffer::from(*part))).unwrap()
+}
+
+#[test]
+fn test_records_across_boundaries() {
+    let mut chain = chain(&[&[0x12, 0x34, 0x56], &[], &[0x78, 0x9A], &[0xBC, 0xDE, 0xF0]]);
+    assert_eq!(chain.source_count(), 4);
+    assert_eq!(chain.len().unwrap(), 8);
+    assert_eq!(chain.locate(3), Some((2, 0)));
+    assert_eq!(chain.locate(6), Some((3, 1)));
+    assert_eq!(chain.locate(8), None);
+
+    assert_eq!(chain.read_u16::<BigEndian>().unwrap(), 0x1234);
+    assert_eq!(chain.read_u32::<BigEndian>().unwrap(), 0x5678_9ABC);
+    assert_eq!(chain.pos().unwrap(), 6);
+    assert_eq!(chain.read_u16::<BigEndian>().unwrap(), 0xDEF0);
+    assert!(chain.is_eof());
+    assert!(chain.read_u8().is_err());
+
+    chain.seek(2).unwrap();
+    let mut data = Vec::new();
+    chain.read_to_end(&mut data).unwrap();
+    assert_eq!(data, vec![0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0]);
+
+    // Seeking past the end is allowed, but nothing can be read there
+    chain.seek(20).unwrap();
+    assert_eq!(chain.read(&mut [0u8; 4]).unwrap(), 0);
+    chain.seek(4).unwrap();
+    assert_eq!(chain.read_u8().unwrap(), 0x9A);
+}
+
+#[test]
+fn test_bit_reader_over_chain() {
+    let chain = chain(&[&[0b1010_1010], &[0b1111_0000, 0x0F], &[0xFF]]);
+    let mut reader = BitReader::with_capacity(chain, 2);
+    assert_eq!(reader.read_bits(4).unwrap(), 0b1010);
+    // A field crossing two segment boundaries
+    assert_eq!(
+        reader.read_bits(24).unwrap(),
+        0b1010_1111_0000_0000_1111_1111
+    );
+    reader.seek_bits(6).unwrap();
+    assert_eq!(reader.read_bits(4).unwrap(), 0b1011);
+}
+
+#[test]
+fn test_open_segments() {
+    let dir = tempdir().unwrap();
+    for (number, data) in [(0, vec![1u8, 2]), (1, vec![3]), (2, vec![4, 5, 6])] {
+        fs::write(dir.path().join(format!("capture.{:03}", number)), data).unwrap();
+    }
+    // A gap ends the sequence
+    fs::write(dir.path().join("capture.004"), [9]).unwrap();
+
+    let mut chain = BinReader::open_segments(dir.path().join("capture.000")).unwrap();
+    assert_eq!(chain.source_count(), 3);
+    let mut data = Vec::new();
+    chain.read_to_end(&mut data).unwrap();
+    assert_eq!(data, vec![1, 2, 3, 4, 5, 6]);
+
+    let mut chain = BinReader::open_chain([
+        dir.path().join("capture.002"),
+        dir.path().join("capture.000"),
+    ])
+    .unwrap();
+    chain.seek(2).unwrap();
+    assert_eq!(chain.read_u16::<BigEndian>().unwrap(), 0x0601);
+
+    assert!(matches!(
+        BinReader::open_segments(dir.path().join("capture.bin")),
+        Err(Error::File(_))
+    ));
+}
@@ -0,0 +1,127 @@
+use binary_data::{BigEndian, BinMemoryBuffer, BinSeek, BinStream, BitReader, ReadBytes};
+
+/// A speculative parse which fails half way through.
+fn parse_tagged(reader: &mut BinMemoryBuffer) -> binary_data::Result<u16> {
+    let mut checkpoint = reader.checkpoint()?;
+    let tag = checkpoint.read_u8()?;
+    let value = checkpoint.read_u16::<BigEndian>()?;
+    if tag != 0x7E {
+        return Err(binary_data::Error::NotSupported);
+    }
+    checkpoint.commit();
+    Ok(value)
+}
+
+#[test]
+fn test_checkpoint_rewinds_on_error() {
+    let mut reader = BinMemoryBuffer::from(vec![0x01, 0x12, 0x34, 0x7E, 0x56, 0x78]);
+    assert!(parse_tagged(&mut reader).is_err());
+    assert_eq!(reader.pos().unwrap(), 0);
+
+    reader.seek(3).unwrap();
+    assert_eq!(parse_tagged(&mut reader).unwrap(), 0x5678);
+    assert_eq!(reader.pos().unwrap(), 6);
+
+    // Running out of data also rewinds
+    reader.seek(4).unwrap();
+    assert!(parse_tagged(&mut reader).is_err());
+    assert_eq!(reader.pos().unwrap(), 4);
+}
+
+#[test]
+fn test_checkpoint_nested() {
+    let mut reader = BinMemoryBuffer::from(vec![1, 2, 3, 4, 5, 6]);
+    {
+        let mut outer = reader.checkpoint().unwrap();
+        outer.read_u8().unwrap();
+        {
+            let mut inner = outer.checkpoint().unwrap();
+            inner.read_u16::<BigEndian>().unwrap();
+            assert_eq!(inner.pos().unwrap(), 3);
+        }
+        assert_eq!(outer.pos().unwrap(), 1);
+        {
+            let mut inner = outer.checkpoint().unwrap();
+            inner.read_u8().unwrap();
+            inner.commit();
+        }
+        assert_eq!(outer.pos().unwrap(), 2);
+        outer.commit();
+    }
+    assert_eq!(reader.pos().unwrap(), 2);
+
+    let mut checkpoint = reader.checkpoint().unwrap();
+    checkpoint.read_u8().unwrap();
+    checkpoint.rollback().unwrap();
+    assert_eq!(reader.pos().unwrap(), 2);
+}
+
+#[test]
+fn test_bit_checkpoint() {
+    let data: Vec<u8> = (0..64u8).collect();
+    let mut bit_reader = BitReader::with_capacity(BinMemoryBuffer::from(data), 8);
+    bit_reader.read_bits(5).unwrap();
+    {
+        let mut outer = bit_reader.checkpoint();
+        outer.read_bits(60).unwrap();
+        {
+            let mut inner = outer.checkpoint();
+            // Read far past the internal buffer
+            for _ in 0..6 {
+                inner.read_bits(64).unwrap();
+            }
+        }
+        assert_eq!(outer.bit_cursor(), 65);
+        let mut inner = outer.checkpoint();
+        inner.read_bits(3).unwrap();
+        inner.commit();
+        assert_eq!(outer.bit_cursor(), 68);
+    }
+    assert_eq!(bit_reader.bit_cursor(), 5);
+
+    let mut checkpoint = bit_reader.checkpoint();
+    checkpoint.read_bits(7).unwrap();
+    checkpoint.commit();
+    assert_eq!(bit_reader.bit_cursor(), 12);
+}
+
+#[test]
+fn test_bit_checkpoint_forward_only() {
+    let data: Vec<u8> = (0..64u8).collect();
+    let mut bit_reader = BitReader::with_capacity(BinStream::new(data.as_slice()), 16);
+    {
+        let mut checkpoint = bit_reader.checkpoint();
+        checkpoint.read_bits(40).unwrap();
+    }
+    // Rewinding inside the buffer works for streams as well
+    assert_eq!(bit_reader.bit_cursor(), 0);
+
+    let mut checkpoint = bit_reader.checkpoint();
+    for _ in 0..4 {
+        checkpoint.read_bits(64).unwrap();
+    }
+    assert!(checkpoint.rollback().is_err());
+}
+
+#[test]
+fn test_bit_checkpoint_forward_only_small_buffer() {
+    let data: Vec<u8> = (0..64u8).map(|i| i.wrapping_mul(37)).collect();
+    let mut bit_reader = BitReader::with_capacity(BinStream::new(data.as_slice()), 4);
+    let mut checkpoint = bit_reader.checkpoint();
+    assert_eq!(checkpoint.read_bits(1).unwrap(), 0);
+    checkpoint.rollback().unwrap();
+    assert_eq!(bit_reader.bit_cursor(), 0);
+
+    // Unaligned checkpoints after the buffer was refilled several times
+    bit_reader.read_bits(40).unwrap();
+    bit_reader.read_bits(37).unwrap();
+    {
+        let mut checkpoint = bit_reader.checkpoint();
+        checkpoint.read_bits(50).unwrap();
+    }
+    assert_eq!(bit_reader.bit_cursor(), 77);
+    let mut checkpoint = bit_reader.checkpoint();
+    let first = checkpoint.read_bits(13).unwrap();
+    checkpoint.rollback().unwrap();
+    assert_eq!(bit_reader.read_bits(13).unwrap(), first);
+}
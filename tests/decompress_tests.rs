@@ -0,0 +1,138 @@
+use std::{fs, io::Write};
+
+use binary_data::{
+    BigEndian, BinDecompressReader, BinMemoryBuffer, BinReader, BinSeek, BitReader, Compression,
+    Error, ReadBytes,
+};
+use flate2::{write::GzEncoder, Compression as GzLevel};
+use tempfile::tempdir;
+
+#[cfg(any(feature = "zstd", feature = "xz"))]
+/// The data compressed in `tests/data/payload.bin.*` with the `zstd` and `xz` tools.
+fn payload() -> Vec<u8> {
+    (0..600u32)
+        .map(|i| ((i * i + 3 * i) % 251) as u8 & 0x3F)
+        .collect()
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), GzLevel::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn records(count: u32) -> Vec<u8> {
+    (0..count).flat_map(|i| (i * 5).to_be_bytes()).collect()
+}
+
+#[test]
+fn test_detect() {
+    assert_eq!(Compression::detect(&[0x1F, 0x8B, 0x08]), Compression::Gzip);
+    assert_eq!(
+        Compression::detect(&[0x28, 0xB5, 0x2F, 0xFD, 0x00]),
+        Compression::Zstd
+    );
+    assert_eq!(
+        Compression::detect(&[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00]),
+        Compression::Xz
+    );
+    assert_eq!(Compression::detect(&[0xFD, 0x37]), Compression::None);
+    assert_eq!(Compression::detect(&[0x76, 0x01]), Compression::None);
+}
+
+#[test]
+fn test_gzip_in_memory_and_plain_files() {
+    let dir = tempdir().unwrap();
+    let data = records(1000);
+    let path = dir.path().join("card.ddd.gz");
+    fs::write(&path, gzip(&data)).unwrap();
+
+    let mut file = BinReader::open_decompressed(&path, 1 << 20).unwrap();
+    assert_eq!(file.compression(), Compression::Gzip);
+    assert!(file.is_in_memory());
+    assert_eq!(file.len().unwrap(), 4000);
+    file.seek(3996).unwrap();
+    assert_eq!(file.read_u32::<BigEndian>().unwrap(), 999 * 5);
+    file.seek(4).unwrap();
+    assert_eq!(file.read_u32::<BigEndian>().unwrap(), 5);
+
+    // Uncompressed files are read as they are
+    let path = dir.path().join("card.ddd");
+    fs::write(&path, &data).unwrap();
+    let mut file = BinReader::open_decompressed(&path, 1 << 20).unwrap();
+    assert_eq!(file.compression(), Compression::None);
+    assert_eq!(file.len().unwrap(), 4000);
+    file.seek(8).unwrap();
+    assert_eq!(file.read_u32::<BigEndian>().unwrap(), 10);
+    assert_eq!(file.pos().unwrap(), 12);
+}
+
+#[test]
+fn test_seek_by_restart() {
+    let data = records(50_000);
+    let mut source = BinMemoryBuffer::from(vec![0xAA, 0xBB]);
+    source.seek(2).unwrap();
+    std::io::Write::write_all(&mut source, &gzip(&data)).unwrap();
+    source.seek(2).unwrap();
+
+    // Too large for the memory limit, so the data is decompressed while reading
+    let mut reader = BinDecompressReader::new(source, 1024).unwrap();
+    assert!(!reader.is_in_memory());
+    assert!(matches!(reader.len(), Err(Error::NotSupported)));
+    reader.seek(150_000).unwrap();
+    assert_eq!(reader.read_u32::<BigEndian>().unwrap(), 37_500 * 5);
+    reader.seek(40).unwrap();
+    assert_eq!(reader.read_u32::<BigEndian>().unwrap(), 50);
+    assert_eq!(reader.pos().unwrap(), 44);
+
+    reader.seek(199_996).unwrap();
+    assert_eq!(reader.read_u32::<BigEndian>().unwrap(), 49_999 * 5);
+    assert!(reader.is_eof());
+    assert_eq!(reader.len().unwrap(), 200_000);
+    assert!(reader.seek(200_001).is_err());
+
+    // Works as a bit source, backward bit seeks restart as well
+    reader.seek(0).unwrap();
+    let mut bits = BitReader::new(reader);
+    bits.seek_bits(8 * 100_000 + 29).unwrap();
+    assert_eq!(bits.read_bits(3).unwrap(), (25_000 * 5) & 0x7);
+    bits.seek_bits(8 * 4 + 29).unwrap();
+    assert_eq!(bits.read_bits(3).unwrap(), 5);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd_reference_vector() {
+    let source = BinMemoryBuffer::from(&include_bytes!("data/payload.bin.zst")[..]);
+    let mut reader = BinDecompressReader::new(source, 0).unwrap();
+    assert_eq!(reader.compression(), Compression::Zstd);
+    let mut data = Vec::new();
+    std::io::Read::read_to_end(&mut reader, &mut data).unwrap();
+    assert_eq!(data, payload());
+    reader.seek(100).unwrap();
+    assert_eq!(reader.read_u8().unwrap(), payload()[100]);
+}
+
+#[cfg(feature = "xz")]
+#[test]
+fn test_xz_reference_vector() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("payload.bin.xz");
+    fs::write(&path, include_bytes!("data/payload.bin.xz")).unwrap();
+    let mut reader = BinReader::open_decompressed(&path, 1 << 20).unwrap();
+    assert_eq!(reader.compression(), Compression::Xz);
+    assert_eq!(reader.len().unwrap(), 600);
+    let mut data = vec![0u8; 600];
+    std::io::Read::read_exact(&mut reader, &mut data).unwrap();
+    assert_eq!(data, payload());
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn test_format_without_feature() {
+    let source = BinMemoryBuffer::from(&include_bytes!("data/payload.bin.zst")[..]);
+    assert!(matches!(
+        BinDecompressReader::new(source, 0),
+        Err(Error::NotSupported)
+    ));
+}
@@ -0,0 +1,67 @@
+use std::{fs, io::ErrorKind};
+
+use binary_data::{BinFile, BinReader, Error, FileLock};
+use tempfile::tempdir;
+
+#[test]
+fn test_exclusive_and_shared_locks() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("archive.bin");
+    fs::write(&path, [0u8; 4]).unwrap();
+
+    let writer = BinFile::options().write(true).open(&path).unwrap();
+    let reader = BinReader::open(&path).unwrap();
+    let other_reader = BinReader::open(&path).unwrap();
+
+    writer.lock_exclusive().unwrap();
+    assert!(!reader.try_lock_shared().unwrap());
+    assert!(!reader.try_lock().unwrap());
+    writer.unlock().unwrap();
+
+    reader.lock_shared().unwrap();
+    assert!(other_reader.try_lock_shared().unwrap());
+    assert!(!writer.try_lock().unwrap());
+    reader.unlock().unwrap();
+    assert!(!writer.try_lock_as(FileLock::Exclusive).unwrap());
+    other_reader.unlock().unwrap();
+    assert!(writer.try_lock().unwrap());
+
+    // Dropping the file releases its lock
+    drop(writer);
+    assert!(reader.try_lock().unwrap());
+}
+
+#[test]
+fn test_lock_at_open() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("archive.bin");
+
+    let writer = BinFile::options()
+        .write(true)
+        .create(true)
+        .lock(FileLock::Exclusive)
+        .open(&path)
+        .unwrap();
+    match BinFile::options()
+        .read(true)
+        .try_lock(FileLock::Shared)
+        .open(&path)
+    {
+        Err(Error::File(err)) => assert_eq!(err.kind(), ErrorKind::WouldBlock),
+        other => panic!("unexpected result {:?}", other),
+    }
+    drop(writer);
+
+    let reader = BinFile::options()
+        .read(true)
+        .try_lock(FileLock::Shared)
+        .open(&path)
+        .unwrap();
+    let other_reader = BinFile::options()
+        .read(true)
+        .lock(FileLock::Shared)
+        .open(&path)
+        .unwrap();
+    assert!(!BinReader::open(&path).unwrap().try_lock().unwrap());
+    drop((reader, other_reader));
+}
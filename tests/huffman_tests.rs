@@ -0,0 +1,125 @@
+use binary_data::{BinMemoryBuffer, BitReader, Error, HuffmanTable};
+
+/// Packs `(code, length)` pairs MSB first, padding the last byte with one bits.
+fn pack_codes(codes: &[(u32, u8)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let (mut acc, mut bits) = (0u64, 0u32);
+    for &(code, len) in codes {
+        acc = (acc << len) | code as u64;
+        bits += len as u32;
+        while bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if bits > 0 {
+        out.push(((acc << (8 - bits)) as u8) | (0xFF >> bits));
+    }
+    out
+}
+
+/// Canonical codes for per-symbol lengths, the reference the decoder must agree with.
+fn canonical_codes(lengths: &[u8]) -> Vec<(u32, u8)> {
+    let mut codes = vec![(0, 0); lengths.len()];
+    let mut code = 0u32;
+    for len in 1..=16u8 {
+        for (symbol, _) in lengths.iter().enumerate().filter(|(_, &l)| l == len) {
+            codes[symbol] = (code, len);
+            code += 1;
+        }
+        code <<= 1;
+    }
+    codes
+}
+
+#[test]
+fn test_jpeg_dc_luminance_table() {
+    let counts = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+    let symbols: Vec<u16> = (0..12).collect();
+    let table = HuffmanTable::from_counts(&counts, &symbols).unwrap();
+    assert_eq!(table.max_code_length(), 9);
+
+    // 00 | 011 | 1110 | 111111110 | 101
+    let mut data = pack_codes(&[
+        (0b00, 2),
+        (0b011, 3),
+        (0b1110, 4),
+        (0b1_1111_1110, 9),
+        (0b101, 3),
+    ]);
+    data.push(0xFF);
+    let mut reader = BitReader::new(BinMemoryBuffer::from(data));
+    let decoded: Vec<u16> = (0..5).map(|_| table.decode(&mut reader).unwrap()).collect();
+    assert_eq!(decoded, vec![0, 2, 6, 11, 4]);
+    assert_eq!(reader.bit_cursor(), 21);
+
+    // The one bits which follow are the reserved all-ones code
+    assert!(matches!(
+        table.decode(&mut reader),
+        Err(Error::InvalidHuffmanCode)
+    ));
+    assert_eq!(reader.bit_cursor(), 21);
+}
+
+#[test]
+fn test_long_codes_from_lengths() {
+    let lengths = [3u8, 0, 1, 14, 4, 12, 13, 5, 6, 7, 8, 9, 10, 11, 14, 2];
+    let table = HuffmanTable::from_lengths(&lengths).unwrap();
+    let codes = canonical_codes(&lengths);
+
+    let message: Vec<u16> = (0..300u32)
+        .map(|i| (i * 7 % 16) as u16)
+        .filter(|&s| lengths[s as usize] > 0)
+        .collect();
+    let packed: Vec<(u32, u8)> = message.iter().map(|&s| codes[s as usize]).collect();
+    let mut reader = BitReader::with_capacity(BinMemoryBuffer::from(pack_codes(&packed)), 5);
+    for &symbol in &message {
+        assert_eq!(table.decode(&mut reader).unwrap(), symbol);
+    }
+}
+
+#[test]
+fn test_malformed_tables() {
+    // Three codes of length one
+    assert!(matches!(
+        HuffmanTable::from_lengths(&[1, 1, 1]),
+        Err(Error::InvalidHuffmanTable)
+    ));
+    // Over-subscribed at a longer length
+    assert!(matches!(
+        HuffmanTable::from_lengths(&[1, 2, 3, 3, 3]),
+        Err(Error::InvalidHuffmanTable)
+    ));
+    assert!(matches!(
+        HuffmanTable::from_lengths(&[17]),
+        Err(Error::InvalidHuffmanTable)
+    ));
+    // Counts do not match the number of symbols
+    assert!(matches!(
+        HuffmanTable::from_counts(&[2, 1], &[1, 2]),
+        Err(Error::InvalidHuffmanTable)
+    ));
+    assert!(matches!(
+        HuffmanTable::from_counts(&[3], &[1, 2, 3]),
+        Err(Error::InvalidHuffmanTable)
+    ));
+    // An empty table can be built but decodes nothing
+    let table = HuffmanTable::from_lengths(&[0, 0]).unwrap();
+    let mut reader = BitReader::new(BinMemoryBuffer::from(vec![0u8]));
+    assert!(matches!(
+        table.decode(&mut reader),
+        Err(Error::InvalidHuffmanCode)
+    ));
+}
+
+#[test]
+fn test_decode_at_end_of_stream() {
+    let table = HuffmanTable::from_lengths(&[1, 2, 3, 3]).unwrap();
+    // 0 | 10 | 110 | 11 (truncated)
+    let mut reader = BitReader::new(BinMemoryBuffer::from(vec![0b0101_1011]));
+    assert_eq!(table.decode(&mut reader).unwrap(), 0);
+    assert_eq!(table.decode(&mut reader).unwrap(), 1);
+    assert_eq!(table.decode(&mut reader).unwrap(), 2);
+    assert!(matches!(table.decode(&mut reader), Err(Error::File(_))));
+    assert_eq!(reader.bit_cursor(), 6);
+}
@@ -0,0 +1,171 @@
+use std::io::{Read, Write};
+
+use binary_data::{BinMemoryBuffer, BinSeek, Error, InflateReader, ReadBytes};
+use flate2::{
+    write::{DeflateEncoder, GzEncoder, ZlibEncoder},
+    Compression,
+};
+
+fn inflate_all<R: Read>(mut reader: R) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// Compressible test data with long and short repeats.
+fn sample_data(len: usize) -> Vec<u8> {
+    let words: [&[u8]; 6] = [
+        b"tachograph ",
+        b"card ",
+        b"\x00\x01\x02",
+        b"activity ",
+        b"x",
+        b"\xFF",
+    ];
+    let mut out = Vec::with_capacity(len);
+    let mut seed = 12345u32;
+    while out.len() < len {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        if seed >> 28 == 0 {
+            // A literal run which does not compress
+            out.extend((0..(seed >> 20 & 0x3F)).map(|i| (seed >> (i % 24)) as u8));
+        } else {
+            out.extend_from_slice(words[(seed >> 16) as usize % words.len()]);
+        }
+    }
+    out.truncate(len);
+    out
+}
+
+#[test]
+fn test_reference_vectors() {
+    let zlib = [
+        0x78, 0x9C, 0xCB, 0x48, 0xCD, 0xC9, 0xC9, 0x57, 0xC8, 0x40, 0x27, 0x01, 0x68, 0x03, 0x08,
+        0xB1,
+    ];
+    assert_eq!(
+        inflate_all(InflateReader::zlib(&zlib[..])).unwrap(),
+        b"hello hello hello hello"
+    );
+
+    let stored = [
+        0x01, 0x0C, 0x00, 0xF3, 0xFF, 0x73, 0x74, 0x6F, 0x72, 0x65, 0x64, 0x20, 0x62, 0x6C, 0x6F,
+        0x63, 0x6B,
+    ];
+    assert_eq!(
+        inflate_all(InflateReader::new(&stored[..])).unwrap(),
+        b"stored block"
+    );
+
+    let fixed = [0x4B, 0x4C, 0x4A, 0x4E, 0x44, 0x45, 0x00];
+    assert_eq!(
+        inflate_all(InflateReader::new(&fixed[..])).unwrap(),
+        b"abcabcabcabcabcabc"
+    );
+
+    // Member with a file name header
+    let gzip = [
+        0x1F, 0x8B, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x02, 0xFF, 0x61, 0x2E, 0x64, 0x64, 0x64,
+        0x00, 0x4B, 0xAF, 0xCA, 0x2C, 0x50, 0x48, 0x49, 0x2C, 0x49, 0xE4, 0x02, 0x00, 0x26, 0x62,
+        0xBF, 0xB1, 0x0A, 0x00, 0x00, 0x00,
+    ];
+    let mut reader = InflateReader::gzip(&gzip[..]);
+    assert_eq!(inflate_all(&mut reader).unwrap(), b"gzip data\n");
+    assert_eq!(reader.total_in(), gzip.len() as u64);
+}
+
+#[test]
+fn test_against_flate2() {
+    for &len in &[0usize, 1, 1000, 300_000] {
+        let data = sample_data(len);
+        for level in [0, 1, 6, 9] {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::new(level));
+            encoder.write_all(&data).unwrap();
+            let raw = encoder.finish().unwrap();
+            assert_eq!(
+                inflate_all(InflateReader::new(raw.as_slice())).unwrap(),
+                data
+            );
+
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(level));
+            encoder.write_all(&data).unwrap();
+            let zlib = encoder.finish().unwrap();
+            assert_eq!(
+                inflate_all(InflateReader::zlib(zlib.as_slice())).unwrap(),
+                data
+            );
+
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+            encoder.write_all(&data).unwrap();
+            let gzip = encoder.finish().unwrap();
+            assert_eq!(
+                inflate_all(InflateReader::gzip(gzip.as_slice())).unwrap(),
+                data
+            );
+        }
+    }
+}
+
+#[test]
+fn test_concatenated_gzip_members() {
+    let mut stream = Vec::new();
+    for part in [&b"first "[..], &b"second"[..]] {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(part).unwrap();
+        stream.extend(encoder.finish().unwrap());
+    }
+    // Trailing padding after the last member is ignored
+    stream.extend_from_slice(&[0, 0, 0, 0]);
+    assert_eq!(
+        inflate_all(InflateReader::gzip(stream.as_slice())).unwrap(),
+        b"first second"
+    );
+}
+
+#[test]
+fn test_into_memory_buffer() {
+    let data = sample_data(5000);
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&data).unwrap();
+    let zlib = encoder.finish().unwrap();
+
+    let mut buffer = InflateReader::zlib(BinMemoryBuffer::from(zlib))
+        .into_memory_buffer()
+        .unwrap();
+    assert_eq!(buffer.len().unwrap(), 5000);
+    buffer.seek(4000).unwrap();
+    assert_eq!(buffer.read_u8().unwrap(), data[4000]);
+}
+
+#[test]
+fn test_corrupted_streams() {
+    let data = sample_data(2000);
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&data).unwrap();
+    let zlib = encoder.finish().unwrap();
+
+    // Wrong Adler-32
+    let mut bad = zlib.clone();
+    let last = bad.len() - 1;
+    bad[last] ^= 0x01;
+    let err = inflate_all(InflateReader::zlib(bad.as_slice())).unwrap_err();
+    assert!(matches!(
+        err.get_ref().and_then(|e| e.downcast_ref::<Error>()),
+        Some(Error::ChecksumMismatch)
+    ));
+
+    // Truncated stream
+    let err = inflate_all(InflateReader::zlib(&zlib[..zlib.len() / 2])).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    // Reserved block type 3
+    let err = inflate_all(InflateReader::new(&[0x07u8, 0x00][..])).unwrap_err();
+    assert!(matches!(
+        err.get_ref().and_then(|e| e.downcast_ref::<Error>()),
+        Some(Error::InvalidDeflateData(_))
+    ));
+
+    // Bad header check
+    assert!(inflate_all(InflateReader::zlib(&[0x78u8, 0x9D, 0x03, 0x00][..])).is_err());
+    assert!(inflate_all(InflateReader::gzip(&zlib[..])).is_err());
+}
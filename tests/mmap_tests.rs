@@ -0,0 +1,56 @@
+#![cfg(feature = "mmap")]
+
+use std::fs;
+
+use binary_data::{BigEndian, BinReader, BinSeek, BitReader, ReadBytes};
+use tempfile::tempdir;
+
+#[test]
+fn test_read_and_slices() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("archive.ddd");
+    fs::write(&path, [0x76, 0x01, 0x12, 0x34, 0x56, 0x78, 0xAA]).unwrap();
+
+    let mut file = BinReader::open_mmap(&path).unwrap();
+    assert_eq!(file.len().unwrap(), 7);
+    assert_eq!(file.read_u16::<BigEndian>().unwrap(), 0x7601);
+    assert_eq!(file.read_slice(2).unwrap(), &[0x12, 0x34]);
+    assert_eq!(file.pos().unwrap(), 4);
+    assert_eq!(file.remaining_slice(), &[0x56, 0x78, 0xAA]);
+    assert_eq!(file.slice(1..3).unwrap(), &[0x01, 0x12]);
+    assert_eq!(file.as_slice().len(), 7);
+
+    assert!(file.slice(5..8).is_err());
+    assert!(file.read_slice(4).is_err());
+    assert_eq!(file.pos().unwrap(), 4);
+
+    file.seek(6).unwrap();
+    assert_eq!(file.read_u8().unwrap(), 0xAA);
+    assert!(file.is_eof());
+    assert!(file.read_u8().is_err());
+}
+
+#[test]
+fn test_bit_reader_over_mmap() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("bits.bin");
+    fs::write(&path, [0b1011_0011, 0b0101_0101]).unwrap();
+
+    let mut reader = BitReader::new(BinReader::open_mmap(&path).unwrap());
+    assert!(reader.read_bit().unwrap());
+    assert_eq!(reader.read_bits(4).unwrap(), 0b0110);
+    reader.seek_bits(14).unwrap();
+    assert_eq!(reader.read_bits(2).unwrap(), 0b01);
+}
+
+#[test]
+fn test_empty_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("empty.bin");
+    fs::write(&path, []).unwrap();
+
+    let mut file = BinReader::open_mmap(&path).unwrap();
+    assert_eq!(file.len().unwrap(), 0);
+    assert!(file.as_slice().is_empty());
+    assert!(file.read_u8().is_err());
+}
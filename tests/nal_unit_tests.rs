@@ -0,0 +1,83 @@
+use std::io::Read;
+
+use binary_data::{AnnexBReader, BinMemoryBuffer, RbspReader};
+
+#[test]
+fn test_rbsp_reader_removes_emulation_prevention() {
+    let raw = [
+        0x65, 0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0xAA, 0x00, 0x00, 0x03,
+    ];
+    let mut reader = RbspReader::new(&raw[..]);
+    let mut rbsp = Vec::new();
+    reader.read_to_end(&mut rbsp).unwrap();
+
+    assert_eq!(
+        rbsp,
+        vec![0x65, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0xAA, 0x00, 0x00]
+    );
+    assert_eq!(reader.emulation_prevention_offsets(), &[3, 7, 10, 14]);
+    assert_eq!(reader.raw_offset(0), 0);
+    assert_eq!(reader.raw_offset(2), 2);
+    assert_eq!(reader.raw_offset(3), 4);
+    assert_eq!(reader.raw_offset(6), 8);
+    assert_eq!(reader.raw_offset(8), 11);
+    assert_eq!(reader.raw_offset(10), 13);
+}
+
+#[test]
+fn test_rbsp_reader_small_reads() {
+    let raw = [0x00, 0x00, 0x03, 0x03, 0x00, 0x00, 0x03, 0x02];
+    let mut reader = RbspReader::new(&raw[..]);
+    let mut rbsp = Vec::new();
+    let mut byte = [0u8; 1];
+    while reader.read(&mut byte).unwrap() == 1 {
+        rbsp.push(byte[0]);
+    }
+    // Only the 0x03 directly after two zero bytes is removed
+    assert_eq!(rbsp, vec![0x00, 0x00, 0x03, 0x00, 0x00, 0x02]);
+}
+
+#[test]
+fn test_annex_b_reader() {
+    let stream = vec![
+        0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0x00, 0x1E, // SPS, 4 byte start code
+        0x00, 0x00, 0x01, 0x68, 0xCE, 0x38, 0x80, // PPS, 3 byte start code
+        0x00, 0x00, 0x00, 0x01, 0x65, 0x88, 0x00, 0x00, 0x03, 0x01, 0x84,
+        0x00, // IDR, trailing zero
+        0x00, 0x00, 0x01, 0x00, 0x00, 0x01, // empty NAL unit
+        0x41, 0x9A, 0x00, 0x00,
+    ];
+    let units: Vec<_> = AnnexBReader::new(BinMemoryBuffer::from(stream))
+        .collect::<binary_data::Result<_>>()
+        .unwrap();
+
+    assert_eq!(units.len(), 4);
+    assert_eq!(units[0].offset(), 4);
+    assert_eq!(units[0].as_slice(), &[0x67, 0x42, 0x00, 0x1E]);
+    assert_eq!(units[0].h264_type(), 7);
+    assert_eq!(units[1].offset(), 11);
+    assert_eq!(units[1].h264_type(), 8);
+    assert_eq!(units[2].offset(), 19);
+    assert_eq!(
+        units[2].as_slice(),
+        &[0x65, 0x88, 0x00, 0x00, 0x03, 0x01, 0x84]
+    );
+    assert_eq!(units[3].offset(), 33);
+    assert_eq!(units[3].as_slice(), &[0x41, 0x9A]);
+
+    // Parse the IDR slice payload through the RBSP
+    let mut bit_reader = units[2].bit_reader();
+    assert_eq!(bit_reader.read_bits(8).unwrap(), 0x65);
+    assert_eq!(bit_reader.read_bits(32).unwrap(), 0x8800_0001);
+    let mut rbsp = units[2].rbsp_reader();
+    let mut payload = Vec::new();
+    rbsp.read_to_end(&mut payload).unwrap();
+    // Map the RBSP position of 0x84 back to the source
+    assert_eq!(units[2].offset() as u64 + rbsp.raw_offset(5), 25);
+}
+
+#[test]
+fn test_annex_b_reader_without_start_code() {
+    let mut reader = AnnexBReader::new(BinMemoryBuffer::from(vec![0x12, 0x00, 0x00, 0x02]));
+    assert!(reader.next_unit().unwrap().is_none());
+}
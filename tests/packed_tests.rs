@@ -0,0 +1,119 @@
+use binary_data::{BinMemoryBuffer, BitOrder, BitReader, BitWriter, Error};
+
+fn samples(count: usize, bits: u8) -> Vec<u16> {
+    let mask = ((1u32 << bits) - 1) as u16;
+    (0..count as u32)
+        .map(|i| (i.wrapping_mul(40_503) >> 3) as u16 & mask)
+        .collect()
+}
+
+/// Reference little-endian bit packing.
+fn pack_lsb_reference(samples: &[u16], bits: u8) -> Vec<u8> {
+    let mut out = vec![0u8; (samples.len() * bits as usize).div_ceil(8)];
+    for (idx, &sample) in samples.iter().enumerate() {
+        for bit in 0..bits as usize {
+            let pos = idx * bits as usize + bit;
+            out[pos / 8] |= (((sample >> bit) & 1) as u8) << (pos % 8);
+        }
+    }
+    out
+}
+
+fn pack(samples: &[u16], bits: u8, order: BitOrder) -> Vec<u8> {
+    let mut writer = BitWriter::new(Vec::new());
+    writer.write_packed(samples, bits, order).unwrap();
+    writer.finish().unwrap()
+}
+
+fn unpack(bytes: Vec<u8>, count: usize, bits: u8, order: BitOrder) -> Vec<u16> {
+    let mut out = vec![0u16; count];
+    let mut reader = BitReader::with_capacity(BinMemoryBuffer::from(bytes), 64);
+    reader.read_packed_into(&mut out, bits, order).unwrap();
+    out
+}
+
+#[test]
+fn test_round_trip_all_widths() {
+    for bits in 1..=16u8 {
+        for &count in &[0usize, 1, 7, 8, 9, 1001] {
+            let input = samples(count, bits);
+
+            let msb = pack(&input, bits, BitOrder::MsbFirst);
+            let mut reference = BitWriter::new(Vec::new());
+            for &sample in &input {
+                reference.write_bits(sample as u64, bits).unwrap();
+            }
+            assert_eq!(msb, reference.finish().unwrap(), "{} bits", bits);
+            assert_eq!(unpack(msb, count, bits, BitOrder::MsbFirst), input);
+
+            let lsb = pack(&input, bits, BitOrder::LsbFirst);
+            assert_eq!(lsb, pack_lsb_reference(&input, bits), "{} bits", bits);
+            assert_eq!(unpack(lsb, count, bits, BitOrder::LsbFirst), input);
+        }
+    }
+}
+
+#[test]
+fn test_mipi_raw10_raw12() {
+    let raw10 = pack(&[0x3FF, 0x000, 0x155, 0x2AA], 10, BitOrder::Mipi);
+    assert_eq!(raw10, vec![0xFF, 0x00, 0x55, 0xAA, 0b1001_0011]);
+    assert_eq!(
+        unpack(raw10, 4, 10, BitOrder::Mipi),
+        vec![0x3FF, 0x000, 0x155, 0x2AA]
+    );
+
+    let raw12 = pack(&[0xABC, 0x123], 12, BitOrder::Mipi);
+    assert_eq!(raw12, vec![0xAB, 0x12, 0x3C]);
+    assert_eq!(unpack(raw12, 2, 12, BitOrder::Mipi), vec![0xABC, 0x123]);
+
+    for bits in [10u8, 12, 14] {
+        let input = samples(4 * 333, bits);
+        let bytes = pack(&input, bits, BitOrder::Mipi);
+        assert_eq!(bytes.len(), input.len() * bits as usize / 8);
+        assert_eq!(unpack(bytes, input.len(), bits, BitOrder::Mipi), input);
+    }
+}
+
+#[test]
+fn test_unaligned_msb_first() {
+    let input = samples(101, 12);
+    let mut writer = BitWriter::new(Vec::new());
+    writer.write_bits(0b101, 3).unwrap();
+    writer.write_packed(&input, 12, BitOrder::MsbFirst).unwrap();
+    writer.write_bits(0b11, 2).unwrap();
+    let bytes = writer.finish().unwrap();
+
+    let mut reader = BitReader::new(BinMemoryBuffer::from(bytes));
+    assert_eq!(reader.read_bits(3).unwrap(), 0b101);
+    let mut out = vec![0u16; 101];
+    reader
+        .read_packed_into(&mut out, 12, BitOrder::MsbFirst)
+        .unwrap();
+    assert_eq!(out, input);
+    assert_eq!(reader.bit_cursor(), 3 + 101 * 12);
+    assert_eq!(reader.read_bits(2).unwrap(), 0b11);
+}
+
+#[test]
+fn test_packed_errors() {
+    let mut reader = BitReader::new(BinMemoryBuffer::from(vec![0u8; 16]));
+    let mut out = [0u16; 3];
+    assert!(matches!(
+        reader.read_packed_into(&mut out, 10, BitOrder::Mipi),
+        Err(Error::NotSupported)
+    ));
+    assert!(matches!(
+        reader.read_packed_into(&mut out, 17, BitOrder::MsbFirst),
+        Err(Error::NotSupported)
+    ));
+    reader.read_bit().unwrap();
+    assert!(matches!(
+        reader.read_packed_into(&mut out, 12, BitOrder::LsbFirst),
+        Err(Error::NotSupported)
+    ));
+    // Not enough data
+    let mut out = [0u16; 20];
+    assert!(reader
+        .read_packed_into(&mut out, 12, BitOrder::MsbFirst)
+        .is_err());
+}
@@ -0,0 +1,257 @@
+use std::io::{ErrorKind, Read, Write};
+
+use binary_data::{BigEndian, BinRingMemoryBuffer, BinSeek, Error, OverflowPolicy, ReadBytes};
+
+#[test]
+fn test_fifo_read_write_heads() {
+    let mut ring = BinRingMemoryBuffer::with_capacity(8);
+    assert_eq!(ring.available(), 0);
+    assert_eq!(ring.free(), 8);
+
+    ring.write_all(&[1, 2, 3, 4, 5, 6]).unwrap();
+    let mut buf = [0u8; 4];
+    assert_eq!(ring.read(&mut buf).unwrap(), 4);
+    assert_eq!(buf, [1, 2, 3, 4]);
+    assert_eq!((ring.read_index(), ring.write_index()), (4, 6));
+
+    // Writing continues after the newest data and wraps around
+    ring.write_all(&[7, 8, 9, 10, 11]).unwrap();
+    assert_eq!(ring.available(), 7);
+    assert_eq!(ring.free(), 1);
+    assert_eq!(ring.write_index(), 3);
+    assert_eq!(ring.as_slices(), (&[5, 6, 7, 8][..], &[9, 10, 11][..]));
+
+    let mut buf = [0u8; 10];
+    assert_eq!(ring.read(&mut buf).unwrap(), 7);
+    assert_eq!(buf[..7], [5, 6, 7, 8, 9, 10, 11]);
+    assert_eq!(ring.available(), 0);
+}
+
+#[test]
+fn test_overwrite_oldest() {
+    let mut ring = BinRingMemoryBuffer::with_capacity(4);
+    assert_eq!(ring.overflow_policy(), OverflowPolicy::Overwrite);
+    ring.write_all(&[1, 2, 3]).unwrap();
+    ring.write_all(&[4, 5, 6]).unwrap();
+    assert!(ring.is_full());
+    assert_eq!(ring.as_slices(), (&[3, 4][..], &[5, 6][..]));
+
+    // A write longer than the buffer keeps its last bytes
+    assert_eq!(ring.write(&[7, 8, 9, 10, 11, 12]).unwrap(), 6);
+    let (first, second) = ring.as_slices();
+    assert_eq!([first, second].concat(), [9, 10, 11, 12]);
+}
+
+#[test]
+fn test_reject_and_block() {
+    let mut ring =
+        BinRingMemoryBuffer::with_capacity(4).with_overflow_policy(OverflowPolicy::Reject);
+    ring.write_all(&[1, 2, 3]).unwrap();
+    let err = ring.write(&[4, 5]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::StorageFull);
+    assert_eq!(ring.as_slices(), (&[1, 2, 3][..], &[][..]));
+    // Read data makes room
+    let mut buf = [0u8; 1];
+    ring.read_exact(&mut buf).unwrap();
+    ring.write_all(&[4, 5]).unwrap();
+    assert_eq!(ring.as_slices(), (&[2, 3, 4][..], &[5][..]));
+
+    ring.set_overflow_policy(OverflowPolicy::Block);
+    assert_eq!(ring.write(&[6]).unwrap_err().kind(), ErrorKind::WouldBlock);
+    ring.read_exact(&mut buf).unwrap();
+    ring.read_exact(&mut buf).unwrap();
+    assert_eq!(ring.write(&[6, 7, 8]).unwrap(), 2);
+    assert_eq!(ring.as_slices(), (&[4][..], &[5, 6, 7][..]));
+}
+
+#[test]
+fn test_from_vec_is_full() {
+    let mut ring = BinRingMemoryBuffer::new_with_offset(vec![1, 2, 3, 4, 5], 3);
+    assert_eq!(ring.available(), 5);
+    assert_eq!(ring.as_slices(), (&[4, 5][..], &[1, 2, 3][..]));
+    let mut data = Vec::new();
+    ring.read_to_end(&mut data).unwrap();
+    assert_eq!(data, [4, 5, 1, 2, 3]);
+}
+
+#[test]
+fn test_reads_stop_at_unread_data() {
+    let mut ring = BinRingMemoryBuffer::with_capacity(8);
+    assert!(ring.is_eof());
+    let mut buf = [0xEEu8; 4];
+    assert_eq!(ring.read(&mut buf).unwrap(), 0);
+    assert_eq!(
+        ring.read_exact(&mut buf).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+    assert_eq!(buf, [0xEE; 4]);
+
+    ring.write_all(&[1, 2, 3]).unwrap();
+    assert!(!ring.is_eof());
+    // A short read_exact consumes nothing
+    assert!(ring.read_exact(&mut buf).is_err());
+    assert_eq!(ring.available(), 3);
+    assert!(ring.read_u32::<BigEndian>().is_err());
+    assert_eq!(ring.read_u16::<BigEndian>().unwrap(), 0x0102);
+
+    // `read` returns what is there
+    assert_eq!(ring.read(&mut buf).unwrap(), 1);
+    assert_eq!(buf[0], 3);
+    assert!(ring.is_eof());
+    assert_eq!(ring.read(&mut buf).unwrap(), 0);
+}
+
+/// A cyclic area of 16 bytes holding records with a 1 byte length header, header included.
+/// The oldest record starts at 10 and the newest one at 2, crossing the end of the area.
+fn record_area() -> BinRingMemoryBuffer {
+    let mut area = vec![0u8; 16];
+    let records: [&[u8]; 3] = [&[3, 0xA1, 0xA2], &[5, 0xB1, 0xB2, 0xB3, 0xB4], &[2, 0xC1]];
+    let mut index = 10;
+    for record in records {
+        for &byte in record {
+            area[index % 16] = byte;
+            index += 1;
+        }
+    }
+    BinRingMemoryBuffer::from(area)
+}
+
+#[test]
+fn test_linearize() {
+    let area = record_area();
+    let mut data = area.linearize(10, 4).unwrap();
+    assert_eq!(
+        data.as_slice(),
+        [3, 0xA1, 0xA2, 5, 0xB1, 0xB2, 0xB3, 0xB4, 2, 0xC1]
+    );
+    assert_eq!(data.read_u16::<BigEndian>().unwrap(), 0x03A1);
+    // Equal pointers take the whole area
+    assert_eq!(area.linearize(10, 10).unwrap().len().unwrap(), 16);
+    assert!(matches!(
+        area.linearize(16, 4),
+        Err(Error::InvalidRecordArea(_))
+    ));
+}
+
+#[test]
+fn test_length_prefixed_records() {
+    let area = record_area();
+    let records = || {
+        area.length_prefixed_records(10, 2, 1, |header| header[0] as usize)
+            .unwrap()
+    };
+    let forward: Vec<Vec<u8>> = records().map(|r| r.as_slice().to_vec()).collect();
+    assert_eq!(
+        forward,
+        [
+            vec![3, 0xA1, 0xA2],
+            vec![5, 0xB1, 0xB2, 0xB3, 0xB4],
+            vec![2, 0xC1]
+        ]
+    );
+    let mut backward = records().rev();
+    assert_eq!(backward.len(), 3);
+    let mut newest = backward.next().unwrap();
+    assert_eq!(newest.read_u8().unwrap(), 2);
+    assert_eq!(
+        backward.next().unwrap().as_slice()[1..],
+        [0xB1, 0xB2, 0xB3, 0xB4]
+    );
+
+    // The newest pointer does not fall on a record
+    assert!(matches!(
+        area.length_prefixed_records(10, 3, 1, |header| header[0] as usize),
+        Err(Error::InvalidRecordArea(_))
+    ));
+}
+
+#[test]
+fn test_fixed_records() {
+    let area = BinRingMemoryBuffer::from((0u8..12).collect::<Vec<_>>());
+    let records: Vec<Vec<u8>> = area
+        .fixed_records(8, 2, 3)
+        .unwrap()
+        .rev()
+        .map(|r| r.as_slice().to_vec())
+        .collect();
+    assert_eq!(records, [vec![2, 3, 4], vec![11, 0, 1], vec![8, 9, 10]]);
+    assert!(area.fixed_records(8, 3, 3).is_err());
+}
+
+#[test]
+fn test_modular_seek() {
+    let mut ring = BinRingMemoryBuffer::new_with_offset((0u8..8).collect(), 6);
+    assert!(ring.is_seekable());
+    assert_eq!(ring.pos().unwrap(), 6);
+    assert_eq!(ring.read_into_vec(4).unwrap(), [6, 7, 0, 1]);
+    // Positions keep counting past the end of the buffer
+    assert_eq!(ring.pos().unwrap(), 10);
+
+    assert_eq!(ring.seek(7).unwrap(), 7);
+    assert_eq!(ring.read_u8().unwrap(), 7);
+    // Positions past the capacity wrap around
+    ring.seek(8 * 3 + 4).unwrap();
+    assert_eq!(ring.read_u8().unwrap(), 4);
+    assert_eq!(ring.available(), 1);
+
+    // Relative seeks wrap backwards over the start of the buffer
+    ring.seek(1).unwrap();
+    ring.seek_relative(-3).unwrap();
+    assert_eq!(ring.read_u8().unwrap(), 6);
+    ring.seek_relative(-2).unwrap();
+    assert_eq!(ring.read_u8().unwrap(), 5);
+    ring.seek_relative(1).unwrap();
+    assert_eq!(ring.read_u8().unwrap(), 7);
+
+    // The checkpoint rewinds across the wrap point
+    {
+        let mut checkpoint = ring.checkpoint().unwrap();
+        assert_eq!(checkpoint.read_into_vec(3).unwrap(), [0, 1, 2]);
+    }
+    assert_eq!(ring.read_u8().unwrap(), 0);
+}
+
+#[test]
+fn test_seek_in_partly_filled_buffer() {
+    let mut ring = BinRingMemoryBuffer::with_capacity(8);
+    ring.write_all(&[1, 2, 3]).unwrap();
+    assert_eq!(ring.read_into_vec(3).unwrap(), [1, 2, 3]);
+    // Data which was read can be read again
+    ring.seek(1).unwrap();
+    assert_eq!(ring.read_u16::<BigEndian>().unwrap(), 0x0203);
+    ring.seek(3).unwrap();
+    assert!(ring.is_eof());
+    // Nothing was written past index 3
+    assert!(ring.seek(5).is_err());
+    assert_eq!(ring.pos().unwrap(), 3);
+}
+
+#[test]
+fn test_pos_after_overwrite() {
+    let mut ring = BinRingMemoryBuffer::with_capacity(8);
+    ring.write_all(&[0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+    assert_eq!(ring.read_into_vec(8).unwrap(), [0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(ring.pos().unwrap(), 8);
+
+    // Bytes 8 and 9 wrap to the start of the buffer
+    ring.write_all(&[8, 9]).unwrap();
+    assert_eq!(ring.pos().unwrap(), 8);
+    assert_eq!(ring.read_u8().unwrap(), 8);
+    let saved = ring.pos().unwrap();
+    assert_eq!(saved, 9);
+
+    // Overwriting moves the oldest byte past the end of the buffer, the position of the
+    // unread byte 9 drops by the capacity
+    ring.write_all(&[10, 11, 12, 13, 14, 15]).unwrap();
+    assert_eq!(ring.available(), 7);
+    assert_eq!(ring.pos().unwrap(), 1);
+    assert_eq!(ring.read_u8().unwrap(), 9);
+
+    // Both the old and the new position reach the same byte
+    ring.seek(saved).unwrap();
+    assert_eq!(ring.read_u8().unwrap(), 9);
+    let pos = ring.pos().unwrap();
+    assert_eq!(ring.read_into_vec(6).unwrap(), [10, 11, 12, 13, 14, 15]);
+    ring.seek(pos).unwrap();
+    assert_eq!(ring.read_u8().unwrap(), 10);
+}
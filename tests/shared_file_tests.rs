@@ -0,0 +1,90 @@
+#![cfg(unix)]
+
+use std::{fs, io::ErrorKind, thread};
+
+use binary_data::{BigEndian, BinFile, BinReader, BinSeek, BitReader, Error, ReadBytes};
+use tempfile::tempdir;
+
+fn records(count: u32) -> Vec<u8> {
+    (0..count).flat_map(|i| (i * 3).to_be_bytes()).collect()
+}
+
+#[test]
+fn test_clones_read_regions_in_parallel() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("records.bin");
+    fs::write(&path, records(4000)).unwrap();
+
+    let shared = BinReader::open_shared(&path).unwrap();
+    assert_eq!(shared.len().unwrap(), 16000);
+    let handles: Vec<_> = (0..4u32)
+        .map(|part| {
+            let mut file = shared.clone();
+            thread::spawn(move || {
+                file.seek(part as usize * 4000).unwrap();
+                for i in 0..1000u32 {
+                    let value = file.read_u32::<BigEndian>().unwrap();
+                    assert_eq!(value, (part * 1000 + i) * 3);
+                }
+                file.pos().unwrap()
+            })
+        })
+        .collect();
+    for (part, handle) in handles.into_iter().enumerate() {
+        assert_eq!(handle.join().unwrap(), (part + 1) * 4000);
+    }
+
+    let mut data = [0u8; 4];
+    shared.read_exact_at(&mut data, 15996).unwrap();
+    assert_eq!(u32::from_be_bytes(data), 3999 * 3);
+    assert!(shared.read_exact_at(&mut data, 15998).is_err());
+    assert_eq!(shared.read_at(&mut data, 15998).unwrap(), 2);
+
+    // A clone keeps its own position and works as a bit source
+    let mut reader = BitReader::new(shared.clone());
+    reader.seek_bits(8 * 4 + 30).unwrap();
+    assert_eq!(reader.read_bits(2).unwrap(), 0b11);
+}
+
+#[test]
+fn test_positional_access_on_bin_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("patch.bin");
+    fs::write(&path, [1, 2, 3, 4]).unwrap();
+
+    let mut file = BinFile::options()
+        .read(true)
+        .write(true)
+        .buffered(16)
+        .open(&path)
+        .unwrap();
+    assert_eq!(file.read_n_bytes::<1>().unwrap(), [1]);
+    let mut data = [0u8; 2];
+    file.read_exact_at(&mut data, 2).unwrap();
+    assert_eq!(data, [3, 4]);
+    assert_eq!(file.pos().unwrap(), 1);
+
+    // The buffered window is dropped, so reads see positional writes
+    file.write_at(&[0x22, 0x33], 1).unwrap();
+    assert_eq!(file.read_n_bytes::<2>().unwrap(), [0x22, 0x33]);
+    file.write_at(&[5, 6], 4).unwrap();
+    assert_eq!(file.len().unwrap(), 6);
+    assert!(file.read_exact_at(&mut data, 5).is_err());
+    // Offsets close to the largest u64 do not overflow
+    let err = file.read_exact_at(&mut data, u64::MAX).unwrap_err();
+    assert!(matches!(err, Error::File(err) if err.kind() == ErrorKind::UnexpectedEof));
+    let err = file.write_at(&[7], u64::MAX).unwrap_err();
+    assert!(matches!(err, Error::File(err) if err.kind() == ErrorKind::InvalidInput));
+    assert_eq!(file.len().unwrap(), 6);
+
+    file.write_bytes(&[0x44]).unwrap();
+    let mut shared = file.into_shared().unwrap();
+    assert_eq!(shared.pos().unwrap(), 4);
+    assert_eq!(shared.read_u16::<BigEndian>().unwrap(), 0x0506);
+    shared.seek(0).unwrap();
+    assert_eq!(shared.read_u32::<BigEndian>().unwrap(), 0x0122_3344);
+
+    fs::write(&path, [0u8; 10]).unwrap();
+    assert_eq!(shared.refresh_len().unwrap(), 10);
+    assert!(shared.read_exact_at(&mut data, u64::MAX - 1).is_err());
+}
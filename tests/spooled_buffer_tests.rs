@@ -0,0 +1,65 @@
+#![cfg(feature = "spool")]
+
+use std::{
+    fs,
+    io::{Read, Write},
+};
+
+use binary_data::{BigEndian, BinSeek, BinSpooledBuffer, ReadBytes, WriteBytes};
+use tempfile::tempdir;
+
+#[test]
+fn test_stays_in_memory_below_threshold() {
+    let mut buffer = BinSpooledBuffer::new(16);
+    buffer.write_all(&[1; 10]).unwrap();
+    buffer.seek(4).unwrap();
+    buffer.write_u32::<BigEndian>(0x0102_0304).unwrap();
+    buffer.write_all(&[2; 8]).unwrap();
+    assert!(!buffer.is_spilled());
+    assert_eq!(buffer.len().unwrap(), 16);
+
+    buffer.seek(4).unwrap();
+    assert_eq!(buffer.read_u32::<BigEndian>().unwrap(), 0x0102_0304);
+}
+
+#[test]
+fn test_spills_past_threshold() {
+    let dir = tempdir().unwrap();
+    let mut buffer = BinSpooledBuffer::new_in(100, dir.path());
+    let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+    buffer.write_all(&data[..60]).unwrap();
+    assert!(!buffer.is_spilled());
+    buffer.write_all(&data[60..]).unwrap();
+    assert!(buffer.is_spilled());
+    assert_eq!(buffer.len().unwrap(), 1000);
+    assert_eq!(buffer.pos().unwrap(), 1000);
+
+    // The temp file is anonymous
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+
+    buffer.seek(10).unwrap();
+    buffer.write_all(&[0xFF; 5]).unwrap();
+    buffer.seek(0).unwrap();
+    let mut read = Vec::new();
+    buffer.read_to_end(&mut read).unwrap();
+    let mut expected = data.clone();
+    expected[10..15].fill(0xFF);
+    assert_eq!(read, expected);
+}
+
+#[test]
+fn test_into_bin_file() {
+    let mut buffer = BinSpooledBuffer::new(1 << 20);
+    buffer.write_all(b"TACHO").unwrap();
+    buffer.seek(1).unwrap();
+
+    let mut file = buffer.into_bin_file().unwrap();
+    assert_eq!(file.pos().unwrap(), 1);
+    assert_eq!(file.len().unwrap(), 5);
+    assert_eq!(file.read_n_bytes::<4>().unwrap(), *b"ACHO");
+    file.write_bytes(b"GRAPH").unwrap();
+    file.seek(0).unwrap();
+    let mut read = Vec::new();
+    file.read_to_end(&mut read).unwrap();
+    assert_eq!(read, b"TACHOGRAPH");
+}